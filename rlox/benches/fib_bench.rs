@@ -0,0 +1,38 @@
+// benches/fib_bench.rs
+//
+// `cargo bench --bench fib_bench` runs the function of recursive-fib.lox with a larger argument,
+// every call looks up `fib` and `n` and creates an environment, which is what dominates the time
+// of the tree-walker.
+
+extern crate rulox;
+
+use std::time::{Duration, Instant};
+
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::run;
+
+const SCRIPT: &str = r#"
+fun fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 2) + fib(n - 1);
+}
+print fib(25);
+"#;
+
+fn main() {
+    let mut times: Vec<Duration> = (0..5)
+        .map(|_| {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_stdout(Box::new(std::io::sink()));
+            let started = Instant::now();
+            run(SCRIPT, &mut interpreter).unwrap();
+            started.elapsed()
+        })
+        .collect();
+    times.sort();
+    println!(
+        "fib(25): {:>8.2?} median, {:>8.2?} fastest",
+        times[times.len() / 2],
+        times[0]
+    );
+}
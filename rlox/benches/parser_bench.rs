@@ -0,0 +1,43 @@
+// benches/parser_bench.rs
+//
+// `cargo bench --bench parser_bench` parses generated scripts of a few megabytes straight from the
+// source and prints the throughput. Tokens are only copied when they end up in the AST, so the
+// time per megabyte should stay close to that of `scanner_bench`.
+
+extern crate rulox;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rulox::tree_walker::parser::Parser;
+
+const CHUNK: &str = r#"// compute the café bill, größe matters
+fun total(price, count) {
+  var sum = price * count;
+  /* a block comment
+     over two lines */
+  if (sum >= 100.5) { print "expensive ✓"; }
+  return sum;
+}
+print total(3, 4);
+"#;
+
+fn script(megabytes: usize) -> String {
+    CHUNK.repeat(megabytes * 1024 * 1024 / CHUNK.len())
+}
+
+fn main() {
+    for megabytes in [1, 4, 16] {
+        let source = script(megabytes);
+        let started = Instant::now();
+        let program = Parser::from_source(black_box(&source)).parse().unwrap();
+        let elapsed = started.elapsed();
+        println!(
+            "{:>3} MB: {:>9} statements in {:>8.2?} ({:.1} MB/s)",
+            megabytes,
+            black_box(program).statements.len(),
+            elapsed,
+            source.len() as f64 / 1024.0 / 1024.0 / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,44 @@
+// benches/scanner_bench.rs
+//
+// `cargo bench --bench scanner_bench` scans generated scripts of a few megabytes and prints the
+// throughput. The time per megabyte should stay flat as the input grows.
+
+extern crate rulox;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rulox::frontend::scanner::Scanner;
+
+const CHUNK: &str = r#"// compute the café bill, größe matters
+fun total(price, count) {
+  var sum = price * count;
+  /* a block comment
+     over two lines */
+  if (sum >= 100.5) { print "expensive ✓"; }
+  return sum;
+}
+print total(3, 4);
+"#;
+
+fn script(megabytes: usize) -> String {
+    CHUNK.repeat(megabytes * 1024 * 1024 / CHUNK.len())
+}
+
+fn main() {
+    for megabytes in [1, 4, 16] {
+        let source = script(megabytes);
+        let started = Instant::now();
+        let tokens = Scanner::build_scanner(black_box(&source))
+            .scan_tokens()
+            .unwrap();
+        let elapsed = started.elapsed();
+        println!(
+            "{:>3} MB: {:>9} tokens in {:>8.2?} ({:.1} MB/s)",
+            megabytes,
+            black_box(tokens).len(),
+            elapsed,
+            source.len() as f64 / 1024.0 / 1024.0 / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,8 @@
+use std::io;
+
+use rulox::debug_adapter::run_debug_adapter;
+
+// debug adapter for editors, speaks DAP over stdin/stdout
+fn main() -> Result<(), io::Error> {
+    run_debug_adapter()
+}
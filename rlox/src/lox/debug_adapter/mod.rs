@@ -0,0 +1,460 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde_json::{json, Value};
+
+use crate::language_server::transport::{read_message, write_message};
+use crate::tree_walker::debugger::{Debugger, PauseReason, StepMode};
+use crate::tree_walker::environment::Environment;
+use crate::tree_walker::interpreter::Interpreter;
+use crate::user_interface::parse;
+use crate::LoxError;
+
+// The interpreter runs on a single thread, DAP still wants an id for it
+const THREAD_ID: u64 = 1;
+
+// Framed connection to the editor, shared between the adapter and the paused interpreter
+struct Connection {
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    seq: u64,
+    // lines by the canonical path of their source, the program or one of its modules
+    breakpoints: BTreeMap<PathBuf, BTreeSet<usize>>,
+}
+
+impl Connection {
+    fn read(&mut self) -> io::Result<Option<Value>> {
+        read_message(&mut self.reader)
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn respond(&mut self, request: &Value, body: Value) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+            "body": body,
+        }))
+    }
+
+    fn respond_error(&mut self, request: &Value, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": message,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({"type": "event", "event": event, "body": body}))
+    }
+
+    // setBreakpoints replaces every breakpoint of the source
+    fn set_breakpoints(&mut self, request: &Value) -> io::Result<()> {
+        let path = Path::new(
+            request["arguments"]["source"]["path"]
+                .as_str()
+                .unwrap_or_default(),
+        );
+        let lines: BTreeSet<usize> = request["arguments"]["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| line as usize)
+            .collect();
+        let breakpoints: Vec<Value> = lines
+            .iter()
+            .map(|line| json!({"verified": true, "line": line}))
+            .collect();
+        self.breakpoints.insert(canonical(path), lines);
+        self.respond(request, json!({"breakpoints": breakpoints}))
+    }
+}
+
+// `print` output of the script is forwarded to the editor as output events, one event per line
+// however many writes it took to print it
+struct OutputEvents {
+    connection: Rc<RefCell<Connection>>,
+    line: Vec<u8>,
+}
+
+impl OutputEvents {
+    fn send(&mut self, end: usize) -> io::Result<()> {
+        let line: Vec<u8> = self.line.drain(..end).collect();
+        let output = String::from_utf8_lossy(&line).to_string();
+        self.connection
+            .borrow_mut()
+            .event("output", json!({"category": "stdout", "output": output}))
+    }
+}
+
+impl Write for OutputEvents {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        while let Some(newline) = self.line.iter().position(|byte| *byte == b'\n') {
+            self.send(newline + 1)?;
+        }
+        Ok(buf.len())
+    }
+
+    // what is left of an unfinished line
+    fn flush(&mut self) -> io::Result<()> {
+        if self.line.is_empty() {
+            return Ok(());
+        }
+        self.send(self.line.len())
+    }
+}
+
+impl Drop for OutputEvents {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+struct Launch {
+    program: String,
+    source: String,
+    stop_on_entry: bool,
+}
+
+// Debug Adapter Protocol server, launches a single `.lox` program and debugs it
+pub struct DebugAdapter {
+    connection: Rc<RefCell<Connection>>,
+}
+
+// Speak DAP over stdin/stdout until the editor disconnects
+pub fn run_debug_adapter() -> io::Result<()> {
+    DebugAdapter::new(
+        Box::new(io::BufReader::new(io::stdin())),
+        Box::new(io::stdout()),
+    )
+    .serve()
+}
+
+impl DebugAdapter {
+    pub fn new(reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> Self {
+        DebugAdapter {
+            connection: Rc::new(RefCell::new(Connection {
+                reader,
+                writer,
+                seq: 0,
+                breakpoints: BTreeMap::new(),
+            })),
+        }
+    }
+
+    pub fn serve(self) -> io::Result<()> {
+        let Some(launch) = self.configure()? else {
+            return Ok(());
+        };
+
+        let exit_code = self.run_program(&launch)?;
+
+        let mut connection = self.connection.borrow_mut();
+        connection.event("exited", json!({"exitCode": exit_code}))?;
+        connection.event("terminated", json!({}))?;
+        // the editor still sends disconnect once it noticed the program is gone
+        while let Some(request) = connection.read()? {
+            let command = request["command"].as_str().unwrap_or_default().to_string();
+            match command.as_str() {
+                "disconnect" => return connection.respond(&request, Value::Null),
+                "threads" => connection.respond(&request, threads())?,
+                _ => connection.respond_error(&request, "the program has terminated")?,
+            }
+        }
+        Ok(())
+    }
+
+    // Handle requests until configurationDone, returns None when the editor disconnected first
+    fn configure(&self) -> io::Result<Option<Launch>> {
+        let mut connection = self.connection.borrow_mut();
+        let mut launch: Option<Launch> = None;
+
+        while let Some(request) = connection.read()? {
+            let command = request["command"].as_str().unwrap_or_default().to_string();
+            match command.as_str() {
+                "initialize" => {
+                    connection.respond(
+                        &request,
+                        json!({
+                            "supportsConfigurationDoneRequest": true,
+                            "supportsEvaluateForHovers": true,
+                        }),
+                    )?;
+                    connection.event("initialized", json!({}))?;
+                }
+                "launch" => {
+                    let arguments = &request["arguments"];
+                    let program = arguments["program"].as_str().unwrap_or_default();
+                    match fs::read_to_string(program) {
+                        Ok(source) => {
+                            launch = Some(Launch {
+                                program: program.to_string(),
+                                source,
+                                stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+                            });
+                            connection.respond(&request, Value::Null)?;
+                        }
+                        Err(e) => connection
+                            .respond_error(&request, &format!("can't read {}: {}", program, e))?,
+                    }
+                }
+                "setBreakpoints" => connection.set_breakpoints(&request)?,
+                "threads" => connection.respond(&request, threads())?,
+                "configurationDone" => {
+                    connection.respond(&request, Value::Null)?;
+                    if launch.is_some() {
+                        return Ok(launch);
+                    }
+                }
+                "disconnect" => {
+                    connection.respond(&request, Value::Null)?;
+                    return Ok(None);
+                }
+                _ => connection.respond_error(&request, &format!("unsupported: {}", command))?,
+            }
+        }
+        Ok(None)
+    }
+
+    // Exit code is the one `lox` would exit with for the same script
+    fn run_program(&self, launch: &Launch) -> io::Result<i64> {
+        let program = match parse(&launch.source) {
+            Ok(program) => program,
+            Err(e) => {
+                self.report_error(&e)?;
+                return Ok(e.exit_code().into());
+            }
+        };
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_script_path(Path::new(&launch.program));
+        interpreter.set_stdout(Box::new(OutputEvents {
+            connection: Rc::clone(&self.connection),
+            line: Vec::new(),
+        }));
+        interpreter.attach_debugger(
+            Box::new(PausedSession {
+                connection: Rc::clone(&self.connection),
+                program: launch.program.clone(),
+                program_path: canonical(Path::new(&launch.program)),
+                scopes: Vec::new(),
+            }),
+            launch.stop_on_entry,
+        );
+
+        match interpreter.interpret(program) {
+            Ok(()) => Ok(0),
+            Err(_) if interpreter.is_terminated() => Ok(0),
+            Err(e) => {
+                self.report_error(&e)?;
+                Ok(e.exit_code().into())
+            }
+        }
+    }
+
+    fn report_error(&self, error: &LoxError) -> io::Result<()> {
+        self.connection.borrow_mut().event(
+            "output",
+            json!({"category": "stderr", "output": format!("{}\n", error)}),
+        )
+    }
+}
+
+// Answers the editor's requests while the interpreter is paused
+struct PausedSession {
+    connection: Rc<RefCell<Connection>>,
+    program: String,
+    // the program's canonical path, breakpoints are keyed by it
+    program_path: PathBuf,
+    // variablesReference - 1 indexes into this list, handed out anew on every pause
+    scopes: Vec<Rc<RefCell<Environment>>>,
+}
+
+impl PausedSession {
+    // Returns Some once the editor asked to resume
+    fn handle(
+        &mut self,
+        interpreter: &mut Interpreter,
+        request: &Value,
+    ) -> io::Result<Option<StepMode>> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+        let mut connection = self.connection.borrow_mut();
+
+        let mode = match command {
+            "continue" => StepMode::Continue,
+            "next" => StepMode::StepOver,
+            "stepIn" => StepMode::StepIn,
+            "stepOut" => StepMode::StepOut,
+            "disconnect" | "terminate" => StepMode::Terminate,
+            "threads" => {
+                connection.respond(request, threads())?;
+                return Ok(None);
+            }
+            "setBreakpoints" => {
+                connection.set_breakpoints(request)?;
+                return Ok(None);
+            }
+            "stackTrace" => {
+                let frames: Vec<Value> = interpreter
+                    .call_stack()
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(id, frame)| {
+                        json!({
+                            "id": id,
+                            "name": frame.function,
+                            "line": frame.line,
+                            "column": 1,
+                            "source": {"path": match &frame.module {
+                                Some(module) => module.display().to_string(),
+                                None => self.program.clone(),
+                            }},
+                        })
+                    })
+                    .collect();
+                let total = frames.len();
+                connection.respond(
+                    request,
+                    json!({"stackFrames": frames, "totalFrames": total}),
+                )?;
+                return Ok(None);
+            }
+            "scopes" => {
+                let frame = arguments["frameId"].as_u64().unwrap_or_default() as usize;
+                let mut scopes = Vec::new();
+                let mut environment = Some(interpreter.frame_environment(frame));
+                while let Some(current) = environment {
+                    let parent = current.borrow().parent_env.clone();
+                    let name = match (&parent, scopes.is_empty()) {
+                        (None, _) => "Globals".to_string(),
+                        (Some(_), true) => "Locals".to_string(),
+                        (Some(_), false) => format!("Enclosing {}", scopes.len()),
+                    };
+                    self.scopes.push(current);
+                    scopes.push(json!({
+                        "name": name,
+                        "variablesReference": self.scopes.len(),
+                        "expensive": false,
+                    }));
+                    environment = parent;
+                }
+                connection.respond(request, json!({"scopes": scopes}))?;
+                return Ok(None);
+            }
+            "variables" => {
+                let reference = arguments["variablesReference"].as_u64().unwrap_or_default();
+                let variables: Vec<Value> = (reference as usize)
+                    .checked_sub(1)
+                    .and_then(|index| self.scopes.get(index))
+                    .map(|scope| scope.borrow().bindings())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(name, value)| {
+                        json!({"name": name, "value": value.as_str(), "variablesReference": 0})
+                    })
+                    .collect();
+                connection.respond(request, json!({"variables": variables}))?;
+                return Ok(None);
+            }
+            "evaluate" => {
+                let expression = arguments["expression"].as_str().unwrap_or_default();
+                let top = interpreter.call_stack().len() - 1;
+                let frame = arguments["frameId"].as_u64().map_or(top, |id| id as usize);
+                // output of the evaluated expression goes through the connection as well
+                drop(connection);
+                let result = interpreter.evaluate_in_frame(expression, frame);
+                let mut connection = self.connection.borrow_mut();
+                match result {
+                    Ok(value) => connection.respond(
+                        request,
+                        json!({"result": value.as_str(), "variablesReference": 0}),
+                    )?,
+                    Err(e) => connection.respond_error(request, &e.to_string())?,
+                }
+                return Ok(None);
+            }
+            _ => {
+                connection.respond_error(request, &format!("unsupported: {}", command))?;
+                return Ok(None);
+            }
+        };
+
+        connection.respond(request, json!({"allThreadsContinued": true}))?;
+        Ok(Some(mode))
+    }
+
+    fn pause(
+        &mut self,
+        interpreter: &mut Interpreter,
+        reason: PauseReason,
+    ) -> io::Result<StepMode> {
+        self.scopes.clear();
+        let reason = match reason {
+            PauseReason::Entry => "entry",
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step => "step",
+        };
+        self.connection.borrow_mut().event(
+            "stopped",
+            json!({"reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true}),
+        )?;
+
+        loop {
+            let request = self.connection.borrow_mut().read()?;
+            let Some(request) = request else {
+                return Ok(StepMode::Terminate);
+            };
+            if let Some(mode) = self.handle(interpreter, &request)? {
+                return Ok(mode);
+            }
+        }
+    }
+}
+
+impl Debugger for PausedSession {
+    fn is_breakpoint(&self, module: Option<&Path>, line: usize) -> bool {
+        let source = module.unwrap_or(&self.program_path);
+        self.connection
+            .borrow()
+            .breakpoints
+            .get(source)
+            .is_some_and(|lines| lines.contains(&line))
+    }
+
+    fn paused(
+        &mut self,
+        interpreter: &mut Interpreter,
+        _line: usize,
+        reason: PauseReason,
+    ) -> StepMode {
+        // a broken connection leaves nobody to resume the program
+        self.pause(interpreter, reason)
+            .unwrap_or(StepMode::Terminate)
+    }
+}
+
+// Modules are known by their canonical path, a source the editor names is looked up the same way
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn threads() -> Value {
+    json!({"threads": [{"id": THREAD_ID, "name": "main"}]})
+}
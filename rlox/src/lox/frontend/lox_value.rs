@@ -7,20 +7,31 @@ use super::lox_callable::LoxCallable;
 #[derive(Debug, Clone)]
 pub enum LoxValue {
     String(String),
-    Integer(f64),
+    Integer(i64),
+    Float(f64),
     Boolean(bool),
     Function(Rc<dyn LoxCallable>),
+    // what a `catch` gets when the interpreter failed, e.g. on an undefined variable
+    Error(Rc<ErrorValue>),
     Nil,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorValue {
+    pub message: String,
+    pub line: usize,
+}
+
 impl Display for LoxValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LoxValue::String(s) => s.fmt(f),
             LoxValue::Integer(num) => num.fmt(f),
+            LoxValue::Float(num) => write!(f, "{}", format_float(*num)),
             LoxValue::Boolean(b) => b.fmt(f),
             LoxValue::Nil => write!(f, "`nil`"),
             LoxValue::Function(fun) => write!(f, "Funciont {:?}", fun),
+            LoxValue::Error(error) => write!(f, "Error at line {}: {}", error.line, error.message),
         }
     }
 }
@@ -30,12 +41,20 @@ impl LoxValue {
         match self {
             LoxValue::String(s) => s.clone(),
             LoxValue::Integer(num) => num.to_string(),
+            // a float always shows that it is one, `3.0` and not `3`
+            LoxValue::Float(num) => format_float(*num),
             LoxValue::Boolean(b) => b.to_string(),
             LoxValue::Nil => String::from("nil"),
             LoxValue::Function(fun) => String::from(fun.name()),
+            LoxValue::Error(error) => error.message.clone(),
         }
     }
 
+    // nil and false are falsey, every other value is truthy
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, LoxValue::Nil | LoxValue::Boolean(false))
+    }
+
     pub fn get_callable(&self) -> Option<Rc<dyn LoxCallable>> {
         match *self {
             LoxValue::Function(ref func) => Some(func.clone()),
@@ -51,24 +70,33 @@ impl PartialEq for LoxValue {
         match (self, other) {
             (Self::String(l0), Self::String(r0)) => l0 == r0,
             (Self::Integer(l0), Self::Integer(r0)) => l0 == r0,
+            (Self::Float(l0), Self::Float(r0)) => l0 == r0,
+            // numbers compare by value, `1 == 1.0`
+            (Self::Integer(l0), Self::Float(r0)) | (Self::Float(r0), Self::Integer(l0)) => {
+                *l0 as f64 == *r0
+            }
             (Self::Boolean(l0), Self::Boolean(r0)) => l0 == r0,
             (Self::Nil, Self::Nil) => true,
-            (Self::Nil, _) => false,
-            (_, Self::Nil) => false,
-            (LoxValue::String(_), LoxValue::Integer(_)) => todo!(),
-            (LoxValue::String(_), LoxValue::Boolean(_)) => todo!(),
-            (LoxValue::String(_), LoxValue::Function(_)) => todo!(),
-            (LoxValue::Integer(_), LoxValue::String(_)) => todo!(),
-            (LoxValue::Integer(_), LoxValue::Boolean(_)) => todo!(),
-            (LoxValue::Integer(_), LoxValue::Function(_)) => todo!(),
-            (LoxValue::Boolean(_), LoxValue::String(_)) => todo!(),
-            (LoxValue::Boolean(_), LoxValue::Integer(_)) => todo!(),
-            (LoxValue::Boolean(_), LoxValue::Function(_)) => todo!(),
-            (LoxValue::Function(_), LoxValue::String(_)) => todo!(),
-            (LoxValue::Function(_), LoxValue::Integer(_)) => todo!(),
-            (LoxValue::Function(_), LoxValue::Boolean(_)) => todo!(),
-            (LoxValue::Function(_), LoxValue::Function(_)) => todo!(),
-            // _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+            // a function is only equal to itself
+            (Self::Function(l0), Self::Function(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Error(l0), Self::Error(r0)) => Rc::ptr_eq(l0, r0),
+            // values of different types are never equal
+            _ => false,
         }
     }
 }
+
+// Infinity and NaN are spelled like in jlox, Rust would print `inf` and `NaN`
+fn format_float(number: f64) -> String {
+    if number.is_nan() {
+        String::from("NaN")
+    } else if number.is_infinite() {
+        String::from(if number > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        })
+    } else {
+        format!("{:?}", number)
+    }
+}
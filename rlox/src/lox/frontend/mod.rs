@@ -1,5 +1,6 @@
 pub mod lox_callable;
 pub mod lox_value;
 pub mod scanner;
+pub mod symbol;
 pub mod token;
 pub mod token_type;
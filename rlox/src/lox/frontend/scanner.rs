@@ -4,7 +4,7 @@ use crate::lox_error::LoxError;
 use crate::ParserError;
 use std::string::String;
 
-use crate::frontend::token::Token;
+use crate::frontend::token::{Token, TokenRef};
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 
@@ -14,109 +14,206 @@ lazy_static! {
     static ref KEYWORDS: HashMap<String, TokenType> = {
         let mut map = HashMap::new();
         map.insert("and".to_string(), TokenType::And);
+        map.insert("catch".to_string(), TokenType::Catch);
         map.insert("class".to_string(), TokenType::Class);
         map.insert("else".to_string(), TokenType::Else);
         map.insert("false".to_string(), TokenType::False);
+        map.insert("finally".to_string(), TokenType::Finally);
         map.insert("for".to_string(), TokenType::For);
         map.insert("fun".to_string(), TokenType::Fun);
         map.insert("if".to_string(), TokenType::If);
+        map.insert("import".to_string(), TokenType::Import);
         map.insert("nil".to_string(), TokenType::Nil);
         map.insert("or".to_string(), TokenType::Or);
         map.insert("print".to_string(), TokenType::Print);
         map.insert("return".to_string(), TokenType::Return);
         map.insert("super".to_string(), TokenType::Super);
         map.insert("this".to_string(), TokenType::This);
+        map.insert("throw".to_string(), TokenType::Throw);
         map.insert("true".to_string(), TokenType::True);
+        map.insert("try".to_string(), TokenType::Try);
         map.insert("var".to_string(), TokenType::Var);
         map.insert("while".to_string(), TokenType::While);
         map
     };
 }
 
-#[allow(dead_code)]
+// reserved words, the REPL offers them for completion
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORDS.keys().map(String::as_str)
+}
+
+// Scans lazily, every call to `next` reads just enough of the source for one token. The tokens
+// borrow their lexemes from the source, nothing is copied until a token gets turned into an owned
+// `Token`. The last item is the `Eof` token or the first error, the scanner is fused after it.
 #[derive(Debug, Clone)]
-pub struct Scanner {
-    source: String,
-    tokens: Vec<Token>,
+pub struct Scanner<'src> {
+    source: &'src str,
+    // byte offsets into the source, always on a char boundary
     start: usize,
     current: usize,
     line: usize,
+    // characters between the start of the line and `current`
+    current_column: usize,
+    // column of the lexeme that is being scanned
+    column: usize,
+    // one entry per `${` that is still open, counting the braces opened inside of it
+    interpolations: Vec<usize>,
+    finished: bool,
 }
 
 // self is instance of Scanner, you call instance methods on self.
 // Self is the type Scanner
-impl Scanner {
-    pub fn build_scanner(source: &String) -> Self {
+impl<'src> Scanner<'src> {
+    pub fn build_scanner(source: &'src str) -> Self {
         Scanner {
-            source: source.to_string(),
-            tokens: Vec::new(),
+            source,
             start: 0,
             current: 0,
-            line: 0,
+            line: 1,
+            current_column: 0,
+            column: 0,
+            interpolations: Vec::new(),
+            finished: false,
         }
     }
 
+    // All the tokens at once, for callers that need to look at them more than once
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, LoxError> {
+        self.map(|token| token.map(Token::from)).collect()
+    }
+
+    fn next_token(&mut self) -> Result<TokenRef<'src>, LoxError> {
         while !self.is_at_end() {
             // We are at the beginning of the next lexeme
             // start = 0 current =5, next lexeme start = 5
             self.start = self.current;
-            self.scan_token()?;
+            self.column = self.current_column;
+            if let Some(token) = self.scan_token()? {
+                return Ok(token);
+            }
+        }
+        if !self.interpolations.is_empty() {
+            return Err(LoxError::ScannerError(ParserError::new(
+                self.line,
+                Loc::Pos(self.current),
+                "Unterminated string interpolation",
+            )));
         }
 
         // add at the end of source code an EOF when is_at_end is true.
         // Not needed but cleaner
-        self.tokens
-            .push(Token::new(Eof, "".to_string(), None, self.line));
-        // clone so the caller has ownership of the tokens
-        // TODO: check if we really need clone
-        Ok(self.tokens.clone())
+        Ok(TokenRef {
+            token_type: Eof,
+            lexeme: "",
+            literal: None,
+            line: self.line,
+            column: self.current_column,
+        })
     }
 
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
 
-    fn scan_token(&mut self) -> Result<(), LoxError> {
-        // match and return to scan_tokens until we reach end of source code
-        match self.advance()? {
-            '(' => self.add_token(LeftParen),
-            ')' => self.add_token(RightParen),
-            '{' => self.add_token(LeftBrace),
-            '}' => self.add_token(RightBrace),
-            ',' => self.add_token(Comma),
-            '.' => self.add_token(Dot),
-            '-' => self.add_token(Minus),
-            '+' => self.add_token(Plus),
-            ';' => self.add_token(Semicolon),
-            '*' => self.add_token(Star),
+    // The next token, None when the lexeme was whitespace or a comment
+    fn scan_token(&mut self) -> Result<Option<TokenRef<'src>>, LoxError> {
+        let token_type = match self.advance()? {
+            '(' => LeftParen,
+            ')' => RightParen,
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                LeftBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                // the brace closes the interpolated expression, the string goes on after it
+                Some(0) => {
+                    self.interpolations.pop();
+                    return self.string().map(Some);
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    RightBrace
+                }
+                None => RightBrace,
+            },
+            ',' => Comma,
+            '.' => Dot,
+            '-' => {
+                if self.is_match('-') {
+                    MinusMinus
+                } else if self.is_match('=') {
+                    MinusEqual
+                } else {
+                    Minus
+                }
+            }
+            '+' => {
+                if self.is_match('+') {
+                    PlusPlus
+                } else if self.is_match('=') {
+                    PlusEqual
+                } else {
+                    Plus
+                }
+            }
+            ';' => Semicolon,
+            '*' => {
+                if self.is_match('*') {
+                    StarStar
+                } else if self.is_match('=') {
+                    StarEqual
+                } else {
+                    Star
+                }
+            }
+            '%' => Percent,
+            '?' => Question,
+            ':' => Colon,
+            // `//` already starts a comment, integer division is spelled `~/`
+            '~' => {
+                if self.is_match('/') {
+                    TildeSlash
+                } else {
+                    Tilde
+                }
+            }
+            '&' => Ampersand,
+            '|' => Pipe,
+            '^' => Caret,
             '!' => {
                 //current is set to +1 after advance call so we match on the the char after !
                 if self.is_match('=') {
-                    self.add_token(BangEqual)
+                    BangEqual
                 } else {
-                    self.add_token(Bang)
+                    Bang
                 }
             }
             '=' => {
                 if self.is_match('=') {
-                    self.add_token(EqualEqual)
+                    EqualEqual
                 } else {
-                    self.add_token(Equal)
+                    Equal
                 }
             }
             '<' => {
-                if self.is_match('=') {
-                    self.add_token(LessEqual)
+                if self.is_match('<') {
+                    LessLess
+                } else if self.is_match('=') {
+                    LessEqual
                 } else {
-                    self.add_token(Less)
+                    Less
                 }
             }
             '>' => {
-                if self.is_match('=') {
-                    self.add_token(GreaterEqual)
+                if self.is_match('>') {
+                    GreaterGreater
+                } else if self.is_match('=') {
+                    GreaterEqual
                 } else {
-                    self.add_token(Greater)
+                    Greater
                 }
             }
 
@@ -126,153 +223,198 @@ impl Scanner {
                     while self.peek() != Some('\n') && !self.is_at_end() {
                         self.advance()?;
                     }
+                    return Ok(None);
                 } else if self.is_match('*') {
                     self.scan_block_comment()?;
+                    return Ok(None);
+                } else if self.is_match('=') {
+                    SlashEqual
                 } else {
-                    self.add_token(Slash);
+                    Slash
                 }
             }
 
             // Ignore whitespaces
-            ' ' | '\r' | '\t' => (),
-            '\n' => self.line += 1,
+            ' ' | '\r' | '\t' => return Ok(None),
+            '\n' => {
+                self.new_line();
+                return Ok(None);
+            }
 
             // String starts with var
-            '"' => self.string()?,
+            '"' => return self.string().map(Some),
 
             c => {
                 if self.check_is_digit(Some(c)) {
-                    self.consume_number()?;
+                    return self.consume_number().map(Some);
                 // alpha chars are indetifiers when they are not between quotation marks
                 } else if self.is_alpha(c) {
-                    self.identifier()?;
-                } else {
-                    return Err(LoxError::ScannerError(ParserError::new(
-                        self.line,
-                        Loc::Pos(self.current),
-                        "Unexpected character",
-                    )));
+                    return Ok(Some(self.identifier()));
                 }
+                return Err(LoxError::ScannerError(ParserError::new(
+                    self.line,
+                    Loc::Pos(self.current),
+                    "Unexpected character",
+                )));
             }
-        }
-        Ok(())
+        };
+        Ok(Some(
+            self.token(token_type, Some(LoxValue::String("".to_string()))),
+        ))
     }
 
-    // Consume the current character and return it,
-    // increase current with one
+    // Consume the current character and return it, current moves past all of its bytes
     fn advance(&mut self) -> Result<char, LoxError> {
-        let current_character = self.source.chars().nth(self.current).ok_or_else(|| {
+        let current_character = self.peek().ok_or_else(|| {
             LoxError::ScannerError(ParserError::new(
                 self.line,
                 Loc::Pos(self.current),
                 "Couldn't consume character at this position",
             ))
         })?;
-        self.current += 1;
+        self.current += current_character.len_utf8();
+        self.current_column += 1;
         Ok(current_character)
     }
 
-    // we're going ot handle literals here later
-    fn add_token(&mut self, ttype: TokenType) {
-        self.add_token_object(ttype, None);
+    // The token for the lexeme between start and current
+    fn token(&self, ttype: TokenType, literal: Option<LoxValue>) -> TokenRef<'src> {
+        let source = self.source;
+        TokenRef {
+            token_type: ttype,
+            lexeme: &source[self.start..self.current],
+            literal,
+            line: self.line,
+            column: self.column,
+        }
     }
 
-    fn add_token_object(&mut self, ttype: TokenType, literal: Option<LoxValue>) {
-        // Comments get consumed until the end of the line
-        // for String the lexeme is different
-        let lexeme = &self.source[self.start..self.current];
-        let token = match literal {
-            None => Token::new(
-                ttype,
-                lexeme.to_string(),
-                Some(LoxValue::String("".to_string())),
-                self.line,
-            ),
-            Some(LoxValue::String(value)) => Token::new(
-                ttype,
-                self.source[self.start + 1..self.current - 1].to_string(),
-                Some(LoxValue::String(value.to_string())),
-                self.line,
-            ),
-            Some(LoxValue::Integer(value)) => Token::new(
-                ttype,
-                lexeme.to_string(),
-                Some(LoxValue::Integer(value)),
-                self.line,
-            ),
-            Some(LoxValue::Boolean(value)) => Token::new(
-                ttype,
-                lexeme.to_owned(),
-                Some(LoxValue::Boolean(value)),
-                self.line,
-            ),
-
-            Some(LoxValue::Nil) => {
-                Token::new(ttype, lexeme.to_owned(), Some(LoxValue::Nil), self.line)
-            }
-            // this will enver get called -> remove Litereal from token?
-            Some(LoxValue::Function(funtion)) => Token::new(
-                ttype,
-                lexeme.to_string(),
-                Some(LoxValue::Function(funtion)),
-                self.line,
-            ),
-        };
-        let tokens = self.tokens.push(token);
-        tokens
+    // called after the '\n' has been consumed, so the next line starts at current
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.current_column = 0;
     }
 
     fn is_match(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        }
-        // Current is set to +1 after advance call so we match on the the char after !
-        if self.source.chars().nth(self.current).unwrap() != expected {
-            return false;
+        // Current is already past the character advance returned, so this looks at the next one
+        if self.peek() != Some(expected) {
+            false
         } else {
-            self.current += 1;
-            return true;
+            self.current += expected.len_utf8();
+            self.current_column += 1;
+            true
         }
     }
 
     fn peek(&self) -> Option<char> {
-        return self.source.chars().nth(self.current);
+        self.source[self.current..].chars().next()
     }
 
-    fn string(&mut self) -> Result<(), LoxError> {
-        // if '"' we skip while loop and jump to self.advance() to consume the closing ".
-        while self.peek() != Some('"') && !self.is_at_end() {
-            if self.peek() == Some('\n') {
-                self.line += 1;
+    // The rest of a string literal after its opening `"`, or after the `}` that closed an
+    // interpolated expression. It ends at the closing quote or at the next `${`.
+    fn string(&mut self) -> Result<TokenRef<'src>, LoxError> {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(LoxError::ScannerError(ParserError::new(
+                        self.line,
+                        Loc::Pos(self.current),
+                        "Unterminated tring",
+                    )))
+                }
+                Some('"') => {
+                    self.advance()?;
+                    return Ok(self.string_token(r#String, value, 1));
+                }
+                Some('$') if self.peek_next() == Some('{') => {
+                    self.advance()?;
+                    self.advance()?;
+                    self.interpolations.push(0);
+                    return Ok(self.string_token(Interpolation, value, 2));
+                }
+                Some('\\') => {
+                    self.advance()?;
+                    value.push(self.escape()?);
+                }
+                Some(c) => {
+                    self.advance()?;
+                    if c == '\n' {
+                        self.new_line();
+                    }
+                    value.push(c);
+                }
             }
-            self.advance()?;
         }
-        if self.is_at_end() {
-            return Err(LoxError::ScannerError(ParserError::new(
-                self.line,
-                Loc::Pos(self.current),
-                "Unterminated tring",
-            )));
+    }
+
+    // The lexeme is the source between the delimiters, the literal has the escapes resolved
+    fn string_token(&self, ttype: TokenType, value: String, closing: usize) -> TokenRef<'src> {
+        let source = self.source;
+        TokenRef {
+            token_type: ttype,
+            lexeme: &source[self.start + 1..self.current - closing],
+            literal: Some(LoxValue::String(value)),
+            line: self.line,
+            column: self.column,
         }
-        // while loop has ended because we hit "
-        self.advance()?; // consume the closing ".
+    }
 
-        //Trim the surrounding quotes
-        let string_value = &self.source[self.start + 1..self.current - 1];
-        self.add_token_object(String, Some(LoxValue::String(string_value.to_string())));
+    // `\n`, `\t`, `\r`, `\0`, `\\`, `\"`, `\$` and `\u{1F600}`, the backslash is already consumed
+    fn escape(&mut self) -> Result<char, LoxError> {
+        let escaped = match self.peek() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"' | '$')) => c,
+            Some('u') => {
+                self.advance()?;
+                return self.unicode_escape();
+            }
+            Some(c) => return Err(self.invalid_escape(&format!("\\{}", c))),
+            None => return Err(self.invalid_escape("\\")),
+        };
+        self.advance()?;
+        Ok(escaped)
+    }
 
-        Ok(())
+    fn unicode_escape(&mut self) -> Result<char, LoxError> {
+        if !self.is_match('{') {
+            return Err(self.invalid_escape("\\u"));
+        }
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_hexdigit) {
+            self.advance()?;
+            digits.push(c);
+        }
+        let escape = format!("\\u{{{}}}", digits);
+        if !self.is_match('}') || digits.is_empty() || digits.len() > 6 {
+            return Err(self.invalid_escape(&escape));
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.invalid_escape(&escape))
+    }
+
+    fn invalid_escape(&self, escape: &str) -> LoxError {
+        LoxError::ScannerError(ParserError::new(
+            self.line,
+            Loc::Lexeme(escape.to_string()),
+            "Invalid escape sequence",
+        ))
     }
 
     fn check_is_digit(&self, c: Option<char>) -> bool {
         if let Some(ch) = c {
-            return ch >= '0' && ch <= '9';
+            ch.is_ascii_digit()
         } else {
             false
         }
     }
 
-    fn consume_number(&mut self) -> Result<(), LoxError> {
+    fn consume_number(&mut self) -> Result<TokenRef<'src>, LoxError> {
         while self.check_is_digit(self.peek()) {
             self.advance()?;
         }
@@ -286,52 +428,53 @@ impl Scanner {
                 self.advance()?;
             }
         }
-        let num = self.source[self.start..self.current].parse::<f64>();
+        // a literal with a fractional part is a float, anything else an integer
+        let lexeme = &self.source[self.start..self.current];
+        let number = if lexeme.contains('.') {
+            lexeme.parse::<f64>().ok().map(LoxValue::Float)
+        } else {
+            lexeme.parse::<i64>().ok().map(LoxValue::Integer)
+        };
 
-        match num {
-            Ok(num) => self.add_token_object(Number, Some(LoxValue::Integer(num))),
-            Err(_) => {
-                return Err(LoxError::ScannerError(ParserError::new(
-                    self.line,
-                    Loc::Pos(self.current),
-                    "Couldn't parse integer",
-                )))
-            }
+        match number {
+            Some(number) => Ok(self.token(Number, Some(number))),
+            None => Err(LoxError::ScannerError(ParserError::new(
+                self.line,
+                Loc::Pos(self.current),
+                "Integer literal is too large",
+            ))),
         }
-        Ok(())
     }
 
     fn peek_next(&self) -> Option<char> {
-        if self.current + 1 >= self.source.len() {
-            return None;
-        }
-        return self.source.chars().nth(self.current + 1);
+        self.source[self.current..].chars().nth(1)
     }
 
-    fn identifier(&mut self) -> Result<(), LoxError> {
-        while self.is_alpha_numeric(self.peek()) {
-            self.advance()?;
+    fn identifier(&mut self) -> TokenRef<'src> {
+        // identifiers only end at a character that can't be part of them, so this can't fail
+        while let Some(c) = self.peek().filter(|&c| self.is_alpha_numeric(Some(c))) {
+            self.current += c.len_utf8();
+            self.current_column += 1;
         }
-        let txt = self.source[self.start..self.current].to_string();
-        let ttype = KEYWORDS.get(&txt);
-        match ttype {
-            None => self.add_token(Identifier),
+        let txt = &self.source[self.start..self.current];
+        match KEYWORDS.get(txt) {
+            None => self.token(Identifier, Some(LoxValue::String("".to_string()))),
             Some(value) => match value {
-                False => self.add_token_object(False, Some(LoxValue::Boolean(false))),
-                True => self.add_token_object(True, Some(LoxValue::Boolean(true))),
-                Nil => self.add_token_object(Nil, Some(LoxValue::Nil)),
-                _ => self.add_token(value.clone()),
+                False => self.token(False, Some(LoxValue::Boolean(false))),
+                True => self.token(True, Some(LoxValue::Boolean(true))),
+                Nil => self.token(Nil, Some(LoxValue::Nil)),
+                _ => self.token(value.clone(), Some(LoxValue::String("".to_string()))),
             },
         }
-        Ok(())
     }
 
+    // identifiers can use letters of any script, `café` or `größe`
     fn is_alpha(&self, c: char) -> bool {
-        return c >= 'a' && c <= 'z' || c >= 'A' && c <= 'Z' || c == '_';
+        c.is_alphabetic() || c == '_'
     }
     fn is_alpha_numeric(&self, option_c: Option<char>) -> bool {
         if let Some(c) = option_c {
-            return self.is_alpha(c) || self.check_is_digit(option_c);
+            self.is_alpha(c) || c.is_numeric()
         } else {
             false
         }
@@ -355,7 +498,7 @@ impl Scanner {
                 Some('\n') => {
                     // consume \n and go to the next line and increase line counter with one
                     self.advance()?;
-                    self.line += 1;
+                    self.new_line();
                 }
                 None => {
                     return Err(LoxError::ScannerError(ParserError::new(
@@ -371,3 +514,16 @@ impl Scanner {
         }
     }
 }
+
+impl<'src> Iterator for Scanner<'src> {
+    type Item = Result<TokenRef<'src>, LoxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        self.finished = !matches!(&token, Ok(token) if token.token_type != Eof);
+        Some(token)
+    }
+}
@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+// An interned identifier. Every occurrence of a name shares one allocation, so environments hash
+// and compare the address of that allocation instead of the characters of the name.
+#[derive(Debug, Clone)]
+pub struct Symbol(Rc<str>);
+
+// The names that are in use. A name is dropped once no symbol refers to it anymore, so a long
+// running process like the language server doesn't keep every identifier it ever parsed.
+#[derive(Default)]
+struct Interner {
+    names: HashSet<Rc<str>>,
+    // size of the table after the last sweep, the next one runs once it doubled
+    swept: usize,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+impl Interner {
+    fn sweep(&mut self) {
+        self.names.retain(|name| Rc::strong_count(name) > 1);
+        self.swept = self.names.len();
+    }
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Self {
+        INTERNER.with(|interner| {
+            let mut interner = interner.borrow_mut();
+            if let Some(name) = interner.names.get(name) {
+                return Symbol(Rc::clone(name));
+            }
+            if interner.names.len() >= (interner.swept * 2).max(1024) {
+                interner.sweep();
+            }
+            let name: Rc<str> = Rc::from(name);
+            interner.names.insert(Rc::clone(&name));
+            Symbol(name)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// Interned names are equal exactly when they share the allocation
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(Rc::as_ptr(&self.0) as *const u8, state)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
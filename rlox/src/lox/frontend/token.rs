@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::frontend::symbol::Symbol;
 use crate::frontend::token_type::TokenType;
 
 #[allow(dead_code)]
@@ -9,6 +10,9 @@ pub struct Token {
     pub lexeme: String,
     pub literal: Option<super::lox_value::LoxValue>,
     pub line: usize,
+    pub column: usize,
+    // interned lexeme of an identifier, so looking it up doesn't hash the string again
+    symbol: Option<Symbol>,
 }
 
 #[allow(unused, dead_code)]
@@ -18,14 +22,70 @@ impl Token {
         lexeme: String,
         literal: Option<super::lox_value::LoxValue>,
         line: usize,
+        column: usize,
     ) -> Self {
+        let symbol = (token_type == TokenType::Identifier).then(|| Symbol::intern(&lexeme));
         Token {
             token_type,
             lexeme,
             literal,
             line,
+            column,
+            symbol,
         }
     }
+
+    pub fn symbol(&self) -> Symbol {
+        self.symbol
+            .clone()
+            .unwrap_or_else(|| Symbol::intern(&self.lexeme))
+    }
+
+    // A token borrowing the lexeme of this one
+    pub fn borrow(&self) -> TokenRef<'_> {
+        TokenRef {
+            token_type: self.token_type.clone(),
+            lexeme: &self.lexeme,
+            literal: self.literal.clone(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+// A token that borrows its lexeme from the source, what the scanner hands out while it streams.
+// `to_token` makes an owned copy for the places that keep tokens around, like the AST.
+#[derive(Debug, Clone)]
+pub struct TokenRef<'src> {
+    pub token_type: TokenType,
+    pub lexeme: &'src str,
+    pub literal: Option<super::lox_value::LoxValue>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TokenRef<'_> {
+    pub fn to_token(&self) -> Token {
+        Token::new(
+            self.token_type.clone(),
+            self.lexeme.to_string(),
+            self.literal.clone(),
+            self.line,
+            self.column,
+        )
+    }
+}
+
+impl From<TokenRef<'_>> for Token {
+    fn from(token: TokenRef<'_>) -> Self {
+        Token::new(
+            token.token_type,
+            token.lexeme.to_string(),
+            token.literal,
+            token.line,
+            token.column,
+        )
+    }
 }
 
 impl fmt::Display for Token {
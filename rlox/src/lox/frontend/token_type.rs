@@ -13,6 +13,13 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Question,
+    Colon,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
     // One or two character tokens.
     Bang,
     BangEqual,
@@ -22,25 +29,42 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    TildeSlash,
+    StarStar,
+    LessLess,
+    GreaterGreater,
+    PlusEqual,
+    PlusPlus,
+    MinusEqual,
+    MinusMinus,
+    StarEqual,
+    SlashEqual,
     // Literals.
     Identifier,
     r#String,
+    // the part of a string in front of a `${`, an expression and the rest of the string follow
+    Interpolation,
     Number,
     // Keywords.
     And,
+    Catch,
     Class,
     Else,
     False,
+    Finally,
     Fun,
     For,
     If,
+    Import,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
     Eof,
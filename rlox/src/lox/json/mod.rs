@@ -0,0 +1,349 @@
+use serde_json::{json, Value};
+
+use crate::frontend::lox_value::LoxValue;
+use crate::frontend::token::Token;
+use crate::frontend::token_type::TokenType;
+use crate::tree_walker::parser::{Ast, Expr, ExprId, FunctionDecl, Program, Stmt};
+
+// Tokens as `[{"type", "lexeme", "literal", "span"}]`, literal is only set for strings and numbers
+pub fn tokens_to_json(tokens: &[Token]) -> Value {
+    Value::Array(
+        tokens
+            .iter()
+            .map(|token| {
+                let literal = match (&token.token_type, &token.literal) {
+                    (
+                        TokenType::String | TokenType::Interpolation | TokenType::Number,
+                        Some(value),
+                    ) => value_to_json(value),
+                    _ => Value::Null,
+                };
+                json!({
+                    "type": format!("{:?}", token.token_type),
+                    "lexeme": token.lexeme,
+                    "literal": literal,
+                    "span": Span::of(token).to_json(),
+                })
+            })
+            .collect(),
+    )
+}
+
+// Statements as `[{"kind", "line", "span", ...children}]`. The span of a node covers the tokens the
+// parser kept for it, keywords and parens it dropped aren't included.
+pub fn ast_to_json(program: &Program) -> Value {
+    stmts_to_json(&program.ast, &program.statements).0
+}
+
+pub fn value_to_json(value: &LoxValue) -> Value {
+    match value {
+        LoxValue::String(s) => json!(s),
+        LoxValue::Integer(number) => json!(number),
+        LoxValue::Float(number) => json!(number),
+        LoxValue::Boolean(b) => json!(b),
+        LoxValue::Nil => Value::Null,
+        LoxValue::Function(fun) => json!(format!("<fn {}>", fun.name())),
+        LoxValue::Error(error) => json!({"message": error.message, "line": error.line}),
+    }
+}
+
+// First and last character covered by a node, lines are 1-based and columns 0-based
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+impl Span {
+    fn of(token: &Token) -> Self {
+        // the lexeme of a string doesn't include its quotes, a part in front of an interpolation
+        // starts after a quote or `}` and ends with `${`
+        let (opening, closing) = match token.token_type {
+            TokenType::String => (1, 1),
+            TokenType::Interpolation => (1, 2),
+            _ => (0, 0),
+        };
+        // a string can span lines, its token is on the line it ends on
+        let lines: Vec<&str> = token.lexeme.split('\n').collect();
+        let last_line = lines[lines.len() - 1].chars().count();
+        let end_column = if lines.len() == 1 {
+            token.column + opening + last_line + closing
+        } else {
+            last_line + closing
+        };
+        Span {
+            start: (token.line + 1 - lines.len(), token.column),
+            end: (token.line, end_column),
+        }
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "start": {"line": self.start.0, "column": self.start.1},
+            "end": {"line": self.end.0, "column": self.end.1},
+        })
+    }
+}
+
+fn merge(spans: impl IntoIterator<Item = Option<Span>>) -> Option<Span> {
+    spans.into_iter().flatten().reduce(|a, b| Span {
+        start: a.start.min(b.start),
+        end: a.end.max(b.end),
+    })
+}
+
+fn node(kind: &str, span: Option<Span>, mut fields: Value) -> (Value, Option<Span>) {
+    fields["kind"] = json!(kind);
+    fields["span"] = span.map_or(Value::Null, Span::to_json);
+    (fields, span)
+}
+
+fn stmt_to_json(ast: &Ast, statement: &Stmt) -> (Value, Option<Span>) {
+    let (mut value, span) = match statement {
+        Stmt::Expression(stmt) => {
+            let (expression, span) = expr_to_json(ast, stmt.expression);
+            node("Expression", span, json!({"expression": expression}))
+        }
+        Stmt::Print(stmt) => {
+            let (expression, span) = expr_to_json(ast, stmt.expression);
+            node("Print", span, json!({"expression": expression}))
+        }
+        Stmt::Var(stmt) => {
+            let (initializer, initializer_span) = match &stmt.initializer {
+                Some(initializer) => expr_to_json(ast, *initializer),
+                None => (Value::Null, None),
+            };
+            node(
+                "Var",
+                merge([Some(Span::of(&stmt.name)), initializer_span]),
+                json!({"name": stmt.name.lexeme, "initializer": initializer}),
+            )
+        }
+        Stmt::Function(fun) => function_to_json(ast, fun),
+        Stmt::If(stmt) => {
+            let (condition, condition_span) = expr_to_json(ast, stmt.condition);
+            let (then_branch, then_span) = stmt_to_json(ast, &stmt.then_branch);
+            let (else_branch, else_span) = match &stmt.else_branch {
+                Some(else_branch) => stmt_to_json(ast, else_branch),
+                None => (Value::Null, None),
+            };
+            node(
+                "If",
+                merge([condition_span, then_span, else_span]),
+                json!({"condition": condition, "then": then_branch, "else": else_branch}),
+            )
+        }
+        Stmt::While(stmt) => {
+            let (condition, condition_span) = expr_to_json(ast, stmt.condition);
+            let (body, body_span) = stmt_to_json(ast, &stmt.body);
+            node(
+                "While",
+                merge([condition_span, body_span]),
+                json!({"condition": condition, "body": body}),
+            )
+        }
+        Stmt::Block(stmt) => {
+            let (statements, span) = stmts_to_json(ast, &stmt.statements);
+            node("Block", span, json!({"statements": statements}))
+        }
+        Stmt::Import(stmt) => {
+            let names = stmt.names.as_ref().map(|names| {
+                names
+                    .iter()
+                    .map(|name| name.lexeme.as_str())
+                    .collect::<Vec<&str>>()
+            });
+            let name_spans = stmt.names.iter().flatten().map(|name| Some(Span::of(name)));
+            node(
+                "Import",
+                merge(
+                    [Some(Span::of(&stmt.keyword)), Some(Span::of(&stmt.path))]
+                        .into_iter()
+                        .chain(name_spans),
+                ),
+                json!({"path": stmt.path.lexeme, "names": names}),
+            )
+        }
+        Stmt::Throw(stmt) => {
+            let (value, value_span) = expr_to_json(ast, stmt.value);
+            node(
+                "Throw",
+                merge([Some(Span::of(&stmt.keyword)), value_span]),
+                json!({"value": value}),
+            )
+        }
+        Stmt::Try(stmt) => {
+            let (body, body_span) = stmts_to_json(ast, &stmt.body);
+            let (catch, catch_span) = match &stmt.catch {
+                Some(catch) => {
+                    let (catch_body, catch_body_span) = stmts_to_json(ast, &catch.body);
+                    (
+                        json!({"name": catch.name.lexeme, "body": catch_body}),
+                        merge([Some(Span::of(&catch.name)), catch_body_span]),
+                    )
+                }
+                None => (Value::Null, None),
+            };
+            let (finally, finally_span) = match &stmt.finally {
+                Some(finally) => stmts_to_json(ast, finally),
+                None => (Value::Null, None),
+            };
+            node(
+                "Try",
+                merge([
+                    Some(Span::of(&stmt.keyword)),
+                    body_span,
+                    catch_span,
+                    finally_span,
+                ]),
+                json!({"body": body, "catch": catch, "finally": finally}),
+            )
+        }
+        Stmt::Return(stmt) => {
+            let (value, value_span) = match &stmt.value {
+                Some(value) => expr_to_json(ast, *value),
+                None => (Value::Null, None),
+            };
+            node(
+                "Return",
+                merge([Some(Span::of(&stmt.keyword)), value_span]),
+                json!({"value": value}),
+            )
+        }
+    };
+    value["line"] = json!(statement.line());
+    (value, span)
+}
+
+fn stmts_to_json(ast: &Ast, statements: &[Stmt]) -> (Value, Option<Span>) {
+    let (values, spans): (Vec<Value>, Vec<Option<Span>>) = statements
+        .iter()
+        .map(|stmt| stmt_to_json(ast, stmt))
+        .unzip();
+    (Value::Array(values), merge(spans))
+}
+
+fn function_to_json(ast: &Ast, fun: &FunctionDecl) -> (Value, Option<Span>) {
+    let (body, body_span) = stmts_to_json(ast, &fun.body);
+    let parameters: Vec<&str> = fun
+        .parameters
+        .iter()
+        .map(|parameter| parameter.lexeme.as_str())
+        .collect();
+    let parameter_spans = fun
+        .parameters
+        .iter()
+        .map(|parameter| Some(Span::of(parameter)));
+    node(
+        "Function",
+        merge(
+            [Some(Span::of(&fun.name)), body_span]
+                .into_iter()
+                .chain(parameter_spans),
+        ),
+        json!({"name": fun.name.lexeme, "parameters": parameters, "body": body}),
+    )
+}
+
+fn expr_to_json(ast: &Ast, id: ExprId) -> (Value, Option<Span>) {
+    match &ast[id] {
+        Expr::Assign(expr) => {
+            let (value, value_span) = expr_to_json(ast, expr.value);
+            node(
+                "Assign",
+                merge([Some(Span::of(&expr.name)), value_span]),
+                json!({"name": expr.name.lexeme, "value": value}),
+            )
+        }
+        Expr::Binary(expr) => {
+            let (left, left_span) = expr_to_json(ast, expr.left);
+            let (right, right_span) = expr_to_json(ast, expr.right);
+            node(
+                "Binary",
+                merge([left_span, Some(Span::of(&expr.operator)), right_span]),
+                json!({"operator": expr.operator.lexeme, "left": left, "right": right}),
+            )
+        }
+        Expr::Logical(expr) => {
+            let (left, left_span) = expr_to_json(ast, expr.left);
+            let (right, right_span) = expr_to_json(ast, expr.right);
+            node(
+                "Logical",
+                merge([left_span, Some(Span::of(&expr.operator)), right_span]),
+                json!({"operator": expr.operator.lexeme, "left": left, "right": right}),
+            )
+        }
+        Expr::Comma(expr) => {
+            let (left, left_span) = expr_to_json(ast, expr.left);
+            let (right, right_span) = expr_to_json(ast, expr.right);
+            node(
+                "Comma",
+                merge([left_span, Some(Span::of(&expr.operator)), right_span]),
+                json!({"left": left, "right": right}),
+            )
+        }
+        Expr::Conditional(expr) => {
+            let (condition, condition_span) = expr_to_json(ast, expr.condition);
+            let (then_branch, then_span) = expr_to_json(ast, expr.then_branch);
+            let (else_branch, else_span) = expr_to_json(ast, expr.else_branch);
+            node(
+                "Conditional",
+                merge([
+                    condition_span,
+                    Some(Span::of(&expr.operator)),
+                    then_span,
+                    else_span,
+                ]),
+                json!({"condition": condition, "then": then_branch, "else": else_branch}),
+            )
+        }
+        Expr::Call(expr) => {
+            let (callee, callee_span) = expr_to_json(ast, expr.callee);
+            let (arguments, argument_spans): (Vec<Value>, Vec<Option<Span>>) = expr
+                .arguments
+                .iter()
+                .map(|&id| expr_to_json(ast, id))
+                .unzip();
+            node(
+                "Call",
+                merge(
+                    [callee_span, Some(Span::of(&expr.paren))]
+                        .into_iter()
+                        .chain(argument_spans),
+                ),
+                json!({"callee": callee, "arguments": arguments}),
+            )
+        }
+        Expr::Grouping(expr) => {
+            let (expression, span) = expr_to_json(ast, expr.expression);
+            node("Grouping", span, json!({"expression": expression}))
+        }
+        Expr::Literal(expr) => node(
+            "Literal",
+            Some(Span::of(&expr.token)),
+            json!({"value": value_to_json(&expr.value)}),
+        ),
+        Expr::Unary(expr) => {
+            let (right, right_span) = expr_to_json(ast, expr.right);
+            node(
+                "Unary",
+                merge([Some(Span::of(&expr.operator)), right_span]),
+                json!({"operator": expr.operator.lexeme, "right": right}),
+            )
+        }
+        Expr::Update(expr) => node(
+            "Update",
+            merge([Some(Span::of(&expr.name)), Some(Span::of(&expr.operator))]),
+            json!({
+                "operator": expr.operator.lexeme,
+                "prefix": expr.prefix,
+                "name": expr.name.lexeme,
+            }),
+        ),
+        Expr::Variable(expr) => node(
+            "Variable",
+            Some(Span::of(&expr.name)),
+            json!({"name": expr.name.lexeme}),
+        ),
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+
+use crate::frontend::token::Token;
+use crate::tree_walker::parser::{Ast, Expr, ExprId, FunctionDecl, Parser, Stmt};
+use crate::{Loc, LoxError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+    Parameter,
+}
+
+// A declaration of a name: `fun`, `var` or a function parameter
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: Span,
+    pub parameters: Vec<String>,
+    pub top_level: bool,
+}
+
+impl Symbol {
+    pub fn arity(&self) -> Option<usize> {
+        match self.kind {
+            SymbolKind::Function => Some(self.parameters.len()),
+            _ => None,
+        }
+    }
+
+    pub fn signature(&self) -> String {
+        match self.kind {
+            SymbolKind::Function => {
+                format!("fun {}({})", self.name, self.parameters.join(", "))
+            }
+            SymbolKind::Variable => format!("var {}", self.name),
+            SymbolKind::Parameter => format!("(parameter) {}", self.name),
+        }
+    }
+}
+
+// Zero based line and column of an identifier, the convention LSP uses. The scanner counts lines
+// from one, so they get shifted when converting from tokens and errors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    fn from_token(token: &Token) -> Self {
+        Span {
+            line: token.line.saturating_sub(1),
+            column: token.column,
+            length: token.lexeme.chars().count(),
+        }
+    }
+
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        self.line == line && column >= self.column && column <= self.column + self.length
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub symbol: usize,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub symbols: Vec<Symbol>,
+    pub references: Vec<Reference>,
+}
+
+impl Analysis {
+    // Scan and parse the source, keep going after syntax errors so a single typo doesn't take
+    // go-to-definition down for the rest of the file. The statements before an invalid character
+    // still get resolved.
+    pub fn new(source: &str) -> Self {
+        let mut analysis = Analysis::default();
+        let mut parser = Parser::from_source(source);
+        let (program, errors) = parser.parse_recovering();
+        for error in &errors {
+            analysis.diagnostics.extend(Diagnostic::from_error(error));
+        }
+
+        Resolver::new(&mut analysis, &program.ast).resolve_program(&program.statements);
+        analysis
+    }
+
+    // Index of the symbol that is declared or referenced at the given position
+    pub fn symbol_at(&self, line: usize, column: usize) -> Option<usize> {
+        if let Some(index) = self
+            .symbols
+            .iter()
+            .position(|symbol| symbol.span.contains(line, column))
+        {
+            return Some(index);
+        }
+        self.references
+            .iter()
+            .find(|reference| reference.span.contains(line, column))
+            .map(|reference| reference.symbol)
+    }
+
+    pub fn references_to(&self, symbol: usize) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |reference| reference.symbol == symbol)
+    }
+
+    pub fn top_level_functions(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.top_level && symbol.kind == SymbolKind::Function)
+    }
+}
+
+impl Diagnostic {
+    fn from_error(error: &LoxError) -> Option<Self> {
+        match error {
+            LoxError::ParserError(e) | LoxError::ScannerError(e) => {
+                let message = match e.location() {
+                    Loc::Lexeme(lexeme) if !lexeme.is_empty() => {
+                        format!("at '{}': {}", lexeme, e.message())
+                    }
+                    _ => e.message().to_string(),
+                };
+                Some(Diagnostic {
+                    line: e.line().saturating_sub(1),
+                    message,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Walks the AST the same way the interpreter creates environments and binds every identifier to
+// the declaration it refers to
+struct Resolver<'a> {
+    analysis: &'a mut Analysis,
+    ast: &'a Ast,
+    scopes: Vec<HashMap<String, usize>>,
+    globals: HashMap<String, usize>,
+    // globals are late bound, so names that aren't declared yet get resolved once the whole
+    // program has been walked
+    unresolved: Vec<(String, Span)>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(analysis: &'a mut Analysis, ast: &'a Ast) -> Self {
+        Resolver {
+            analysis,
+            ast,
+            scopes: Vec::new(),
+            globals: HashMap::new(),
+            unresolved: Vec::new(),
+        }
+    }
+
+    fn resolve_program(mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+        for (name, span) in std::mem::take(&mut self.unresolved) {
+            if let Some(&symbol) = self.globals.get(&name) {
+                self.analysis.references.push(Reference { symbol, span });
+            }
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Expression(stmt) => self.resolve_expression(stmt.expression),
+            Stmt::Print(stmt) => self.resolve_expression(stmt.expression),
+            Stmt::Function(fun) => self.resolve_function(fun),
+            Stmt::Var(stmt) => {
+                if let Some(initializer) = stmt.initializer {
+                    self.resolve_expression(initializer);
+                }
+                self.declare(&stmt.name, SymbolKind::Variable, Vec::new());
+            }
+            Stmt::If(stmt) => {
+                self.resolve_expression(stmt.condition);
+                self.resolve_statement(&stmt.then_branch);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::Return(stmt) => {
+                if let Some(value) = stmt.value {
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Block(stmt) => {
+                self.scopes.push(HashMap::new());
+                for statement in &stmt.statements {
+                    self.resolve_statement(statement);
+                }
+                self.scopes.pop();
+            }
+            Stmt::While(stmt) => {
+                self.resolve_expression(stmt.condition);
+                self.resolve_statement(&stmt.body);
+            }
+            Stmt::Import(stmt) => {
+                for name in stmt.names.iter().flatten() {
+                    self.declare(name, SymbolKind::Variable, Vec::new());
+                }
+            }
+            Stmt::Throw(stmt) => self.resolve_expression(stmt.value),
+            Stmt::Try(stmt) => {
+                self.resolve_block(&stmt.body, None);
+                if let Some(catch) = &stmt.catch {
+                    self.resolve_block(&catch.body, Some(&catch.name));
+                }
+                if let Some(finally) = &stmt.finally {
+                    self.resolve_block(finally, None);
+                }
+            }
+        }
+    }
+
+    // A block of a try statement, the catch block declares the caught value in its scope
+    fn resolve_block(&mut self, statements: &[Stmt], caught: Option<&Token>) {
+        self.scopes.push(HashMap::new());
+        if let Some(name) = caught {
+            self.declare(name, SymbolKind::Variable, Vec::new());
+        }
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn resolve_function(&mut self, fun: &FunctionDecl) {
+        let parameters = fun
+            .parameters
+            .iter()
+            .map(|parameter| parameter.lexeme.clone())
+            .collect();
+        // declare the name before the body so recursive calls resolve to the function itself
+        self.declare(&fun.name, SymbolKind::Function, parameters);
+
+        self.scopes.push(HashMap::new());
+        for parameter in &fun.parameters {
+            self.declare(parameter, SymbolKind::Parameter, Vec::new());
+        }
+        for statement in fun.body.iter() {
+            self.resolve_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn resolve_expression(&mut self, id: ExprId) {
+        let ast = self.ast;
+        match &ast[id] {
+            Expr::Assign(expr) => {
+                self.resolve_expression(expr.value);
+                self.reference(&expr.name);
+            }
+            Expr::Binary(expr) => {
+                self.resolve_expression(expr.left);
+                self.resolve_expression(expr.right);
+            }
+            Expr::Logical(expr) => {
+                self.resolve_expression(expr.left);
+                self.resolve_expression(expr.right);
+            }
+            Expr::Comma(expr) => {
+                self.resolve_expression(expr.left);
+                self.resolve_expression(expr.right);
+            }
+            Expr::Conditional(expr) => {
+                self.resolve_expression(expr.condition);
+                self.resolve_expression(expr.then_branch);
+                self.resolve_expression(expr.else_branch);
+            }
+            Expr::Call(expr) => {
+                self.resolve_expression(expr.callee);
+                for &argument in &expr.arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            Expr::Grouping(expr) => self.resolve_expression(expr.expression),
+            Expr::Unary(expr) => self.resolve_expression(expr.right),
+            Expr::Update(expr) => self.reference(&expr.name),
+            Expr::Variable(expr) => self.reference(&expr.name),
+            Expr::Literal(_) => (),
+        }
+    }
+
+    fn declare(&mut self, name: &Token, kind: SymbolKind, parameters: Vec<String>) {
+        let index = self.analysis.symbols.len();
+        self.analysis.symbols.push(Symbol {
+            name: name.lexeme.clone(),
+            kind,
+            span: Span::from_token(name),
+            parameters,
+            top_level: self.scopes.is_empty(),
+        });
+
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.insert(name.lexeme.clone(), index);
+            }
+            None => {
+                self.globals.entry(name.lexeme.clone()).or_insert(index);
+            }
+        }
+    }
+
+    fn reference(&mut self, name: &Token) {
+        let span = Span::from_token(name);
+        let local = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme).copied());
+
+        match local {
+            Some(symbol) => self.analysis.references.push(Reference { symbol, span }),
+            None => self.unresolved.push((name.lexeme.clone(), span)),
+        }
+    }
+}
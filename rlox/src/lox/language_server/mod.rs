@@ -0,0 +1,271 @@
+pub mod analysis;
+pub mod transport;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use analysis::{Analysis, Span};
+use transport::{read_frame, write_message};
+
+// JSON-RPC error codes used by the LSP specification
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// LSP SymbolKind.Function
+const FUNCTION_SYMBOL_KIND: u8 = 12;
+
+struct Document {
+    analysis: Analysis,
+}
+
+#[derive(Default)]
+pub struct LanguageServer {
+    documents: HashMap<String, Document>,
+    shutdown_requested: bool,
+}
+
+// Speak LSP over stdin/stdout until the client sends `exit`, returns the process exit code
+pub fn run_server() -> io::Result<i32> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    LanguageServer::default().serve(&mut stdin.lock(), &mut stdout.lock())
+}
+
+impl LanguageServer {
+    pub fn serve<R: BufRead, W: Write>(
+        &mut self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> io::Result<i32> {
+        while let Some(body) = read_frame(reader)? {
+            let message: Value = match serde_json::from_slice(&body) {
+                Ok(message) => message,
+                // without a request there is no id to answer to, JSON-RPC uses null
+                Err(e) => {
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "id": Value::Null,
+                        "error": {"code": PARSE_ERROR, "message": e.to_string()},
+                    });
+                    write_message(writer, &response)?;
+                    continue;
+                }
+            };
+            let method = match message["method"].as_str() {
+                Some(method) => method.to_string(),
+                // responses to requests we never send
+                None => continue,
+            };
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match message.get("id") {
+                Some(id) => {
+                    let response = match self.handle_request(&method, &params) {
+                        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                        Err((code, error_message)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": {"code": code, "message": error_message},
+                        }),
+                    };
+                    write_message(writer, &response)?;
+                }
+                None => {
+                    if method == "exit" {
+                        return Ok(if self.shutdown_requested { 0 } else { 1 });
+                    }
+                    for notification in self.handle_notification(&method, &params) {
+                        write_message(writer, &notification)?;
+                    }
+                }
+            }
+        }
+        Ok(if self.shutdown_requested { 0 } else { 1 })
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": {"name": "lox-lsp", "version": env!("CARGO_PKG_VERSION")},
+            })),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => {
+                let (uri, document, symbol) = self.symbol_at_position(params)?;
+                Ok(symbol.map_or(Value::Null, |symbol| {
+                    location(uri, &document.analysis.symbols[symbol].span)
+                }))
+            }
+            "textDocument/references" => {
+                let (uri, document, symbol) = self.symbol_at_position(params)?;
+                let Some(symbol) = symbol else {
+                    return Ok(json!([]));
+                };
+                let mut locations = Vec::new();
+                if params["context"]["includeDeclaration"]
+                    .as_bool()
+                    .unwrap_or(true)
+                {
+                    locations.push(location(uri, &document.analysis.symbols[symbol].span));
+                }
+                locations.extend(
+                    document
+                        .analysis
+                        .references_to(symbol)
+                        .map(|reference| location(uri, &reference.span)),
+                );
+                Ok(Value::Array(locations))
+            }
+            "textDocument/hover" => {
+                let (_, document, symbol) = self.symbol_at_position(params)?;
+                Ok(symbol.map_or(Value::Null, |symbol| {
+                    let symbol = &document.analysis.symbols[symbol];
+                    let mut value = format!("```lox\n{}\n```", symbol.signature());
+                    if let Some(arity) = symbol.arity() {
+                        value.push_str(&format!("\narity: {}", arity));
+                    }
+                    json!({"contents": {"kind": "markdown", "value": value}})
+                }))
+            }
+            "textDocument/documentSymbol" => {
+                let uri = text_document_uri(params)?;
+                let Some(document) = self.documents.get(uri) else {
+                    return Ok(json!([]));
+                };
+                let symbols = document
+                    .analysis
+                    .top_level_functions()
+                    .map(|symbol| {
+                        json!({
+                            "name": symbol.name,
+                            "detail": symbol.signature(),
+                            "kind": FUNCTION_SYMBOL_KIND,
+                            "range": range(&symbol.span),
+                            "selectionRange": range(&symbol.span),
+                        })
+                    })
+                    .collect();
+                Ok(Value::Array(symbols))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unhandled method: {}", method))),
+        }
+    }
+
+    // Returns the notifications that have to be sent back to the client
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = match params["textDocument"]["uri"].as_str() {
+            Some(uri) => uri.to_string(),
+            None => return Vec::new(),
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                vec![self.update_document(uri, text.to_string())]
+            }
+            "textDocument/didChange" => {
+                // we only advertise full document sync, so the last change holds the whole text
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                match text {
+                    Some(text) => vec![self.update_document(uri, text.to_string())],
+                    None => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics(&uri, Vec::new())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn update_document(&mut self, uri: String, text: String) -> Value {
+        let analysis = Analysis::new(&text);
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let line_length = text
+                    .lines()
+                    .nth(diagnostic.line)
+                    .map_or(0, |line| line.chars().count());
+                json!({
+                    "range": {
+                        "start": {"line": diagnostic.line, "character": 0},
+                        "end": {"line": diagnostic.line, "character": line_length},
+                    },
+                    // DiagnosticSeverity.Error
+                    "severity": 1,
+                    "source": "lox",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+        let notification = publish_diagnostics(&uri, diagnostics);
+        self.documents.insert(uri, Document { analysis });
+        notification
+    }
+
+    fn symbol_at_position<'a>(
+        &'a self,
+        params: &'a Value,
+    ) -> Result<(&'a str, &'a Document, Option<usize>), (i64, String)> {
+        let uri = text_document_uri(params)?;
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown document: {}", uri)))?;
+        let line = params["position"]["line"].as_u64();
+        let character = params["position"]["character"].as_u64();
+        match (line, character) {
+            (Some(line), Some(character)) => Ok((
+                uri,
+                document,
+                document
+                    .analysis
+                    .symbol_at(line as usize, character as usize),
+            )),
+            _ => Err((INVALID_PARAMS, "missing position".to_string())),
+        }
+    }
+}
+
+fn text_document_uri(params: &Value) -> Result<&str, (i64, String)> {
+    params["textDocument"]["uri"]
+        .as_str()
+        .ok_or_else(|| (INVALID_PARAMS, "missing textDocument.uri".to_string()))
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": {"uri": uri, "diagnostics": diagnostics},
+    })
+}
+
+fn range(span: &Span) -> Value {
+    json!({
+        "start": {"line": span.line, "character": span.column},
+        "end": {"line": span.line, "character": span.column + span.length},
+    })
+}
+
+fn location(uri: &str, span: &Span) -> Value {
+    json!({"uri": uri, "range": range(span)})
+}
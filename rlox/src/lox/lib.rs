@@ -1,5 +1,9 @@
 pub use lox_error::*;
 pub mod user_interface;
+pub mod debug_adapter;
+pub mod json;
+pub mod language_server;
+pub mod lint;
 pub mod tree_walker;
-mod frontend;
+pub mod frontend;
 mod lox_error;
@@ -0,0 +1,450 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::frontend::token::Token;
+use crate::tree_walker::parser::{Ast, Expr, ExprId, FunctionDecl, Program, Stmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariable,
+    SelfInitializer,
+    Shadowing,
+    UnreachableCode,
+    DuplicateParameter,
+    ArityMismatch,
+    PrintFunction,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 7] = [
+        Lint::UnusedVariable,
+        Lint::SelfInitializer,
+        Lint::Shadowing,
+        Lint::UnreachableCode,
+        Lint::DuplicateParameter,
+        Lint::ArityMismatch,
+        Lint::PrintFunction,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::SelfInitializer => "self-initializer",
+            Lint::Shadowing => "shadowing",
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::DuplicateParameter => "duplicate-parameter",
+            Lint::ArityMismatch => "arity-mismatch",
+            Lint::PrintFunction => "print-function",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+// Every lint is enabled unless it gets switched off
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    disabled: HashSet<Lint>,
+}
+
+impl LintConfig {
+    pub fn enable(&mut self, lint: Lint) {
+        self.disabled.remove(&lint);
+    }
+
+    pub fn disable(&mut self, lint: Lint) {
+        self.disabled.insert(lint);
+    }
+
+    pub fn is_enabled(&self, lint: Lint) -> bool {
+        !self.disabled.contains(&lint)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] Warning({}): {}",
+            self.line,
+            self.lint.name(),
+            self.message
+        )
+    }
+}
+
+// Walk the program without running it and report suspicious code, sorted by line
+pub fn lint(program: &Program, config: &LintConfig) -> Vec<Warning> {
+    let statements = &program.statements;
+    let mut linter = Linter {
+        config,
+        ast: &program.ast,
+        scopes: vec![HashMap::new()],
+        warnings: Vec::new(),
+    };
+    linter.hoist_functions(statements);
+    linter.lint_statements(statements);
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.line);
+    warnings
+}
+
+#[derive(Debug, PartialEq)]
+enum BindingKind {
+    Variable,
+    Parameter,
+    Function,
+}
+
+#[derive(Debug)]
+struct Binding {
+    line: usize,
+    kind: BindingKind,
+    used: bool,
+    // false while the initializer of a `var` is being walked
+    initialized: bool,
+    // known as long as a function name hasn't been reassigned
+    arity: Option<usize>,
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    ast: &'a Ast,
+    // scopes[0] holds the globals, every other scope mirrors an environment of the interpreter
+    scopes: Vec<HashMap<String, Binding>>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Linter<'a> {
+    // globals are late bound, so a function body may call a function that is declared further down
+    fn hoist_functions(&mut self, statements: &[Stmt]) {
+        let mut seen = HashSet::new();
+        for statement in statements {
+            if let Stmt::Function(fun) = statement {
+                let arity = if seen.insert(fun.name.lexeme.clone()) {
+                    Some(fun.parameters.len())
+                } else {
+                    None
+                };
+                self.scopes[0].insert(
+                    fun.name.lexeme.clone(),
+                    Binding {
+                        line: fun.name.line,
+                        kind: BindingKind::Function,
+                        used: false,
+                        initialized: true,
+                        arity,
+                    },
+                );
+            }
+        }
+    }
+
+    fn lint_statements(&mut self, statements: &[Stmt]) {
+        let mut reported = false;
+        let mut returned = false;
+
+        for statement in statements {
+            if returned && !reported {
+                self.warn(
+                    Lint::UnreachableCode,
+                    statement.line(),
+                    "unreachable statement after return".to_string(),
+                );
+                reported = true;
+            }
+            self.lint_statement(statement);
+            returned = returned || always_returns(statement);
+        }
+    }
+
+    fn lint_statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Expression(stmt) => self.lint_expression(stmt.expression),
+            Stmt::Print(stmt) => {
+                if let Some(name) = self.function_value(stmt.expression) {
+                    self.warn(
+                        Lint::PrintFunction,
+                        name.line,
+                        format!(
+                            "printing the function `{}` instead of calling it",
+                            name.lexeme
+                        ),
+                    );
+                }
+                self.lint_expression(stmt.expression);
+            }
+            Stmt::Function(fun) => self.lint_function(fun),
+            Stmt::Var(stmt) => {
+                // declare first so reading the variable in its own initializer can be detected
+                self.declare(&stmt.name, BindingKind::Variable, None);
+                if let Some(initializer) = stmt.initializer {
+                    self.lint_expression(initializer);
+                }
+                if let Some(binding) = self.scopes.last_mut().unwrap().get_mut(&stmt.name.lexeme) {
+                    binding.initialized = true;
+                }
+            }
+            Stmt::If(stmt) => {
+                self.lint_expression(stmt.condition);
+                self.lint_statement(&stmt.then_branch);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.lint_statement(else_branch);
+                }
+            }
+            Stmt::Return(stmt) => {
+                if let Some(value) = stmt.value {
+                    self.lint_expression(value);
+                }
+            }
+            Stmt::Block(stmt) => {
+                self.begin_scope();
+                self.lint_statements(&stmt.statements);
+                self.end_scope();
+            }
+            Stmt::While(stmt) => {
+                self.lint_expression(stmt.condition);
+                self.lint_statement(&stmt.body);
+            }
+            Stmt::Import(stmt) => {
+                for name in stmt.names.iter().flatten() {
+                    self.declare(name, BindingKind::Variable, None);
+                }
+            }
+            Stmt::Throw(stmt) => self.lint_expression(stmt.value),
+            Stmt::Try(stmt) => {
+                self.begin_scope();
+                self.lint_statements(&stmt.body);
+                self.end_scope();
+                if let Some(catch) = &stmt.catch {
+                    self.begin_scope();
+                    self.declare(&catch.name, BindingKind::Parameter, None);
+                    self.lint_statements(&catch.body);
+                    self.end_scope();
+                }
+                if let Some(finally) = &stmt.finally {
+                    self.begin_scope();
+                    self.lint_statements(finally);
+                    self.end_scope();
+                }
+            }
+        }
+    }
+
+    fn lint_function(&mut self, fun: &FunctionDecl) {
+        self.declare(&fun.name, BindingKind::Function, Some(fun.parameters.len()));
+
+        self.begin_scope();
+        let mut seen: HashSet<&str> = HashSet::new();
+        for parameter in &fun.parameters {
+            if !seen.insert(&parameter.lexeme) {
+                self.warn(
+                    Lint::DuplicateParameter,
+                    parameter.line,
+                    format!(
+                        "parameter `{}` is declared more than once in `{}`",
+                        parameter.lexeme, fun.name.lexeme
+                    ),
+                );
+                continue;
+            }
+            self.declare(parameter, BindingKind::Parameter, None);
+        }
+        self.lint_statements(&fun.body);
+        self.end_scope();
+    }
+
+    fn lint_expression(&mut self, id: ExprId) {
+        let ast = self.ast;
+        match &ast[id] {
+            Expr::Assign(expr) => {
+                self.lint_expression(expr.value);
+                // once reassigned we can't know statically what gets called anymore
+                if let Some(binding) = self.lookup(&expr.name.lexeme) {
+                    binding.arity = None;
+                }
+            }
+            Expr::Binary(expr) => {
+                self.lint_expression(expr.left);
+                self.lint_expression(expr.right);
+            }
+            Expr::Logical(expr) => {
+                self.lint_expression(expr.left);
+                self.lint_expression(expr.right);
+            }
+            Expr::Comma(expr) => {
+                self.lint_expression(expr.left);
+                self.lint_expression(expr.right);
+            }
+            Expr::Conditional(expr) => {
+                self.lint_expression(expr.condition);
+                self.lint_expression(expr.then_branch);
+                self.lint_expression(expr.else_branch);
+            }
+            Expr::Call(expr) => {
+                if let Expr::Variable(callee) = &ast[expr.callee] {
+                    let arity = self
+                        .lookup(&callee.name.lexeme)
+                        .and_then(|binding| binding.arity);
+                    if let Some(arity) = arity.filter(|&arity| arity != expr.arguments.len()) {
+                        self.warn(
+                            Lint::ArityMismatch,
+                            expr.paren.line,
+                            format!(
+                                "`{}` expects {} arguments but is called with {}",
+                                callee.name.lexeme,
+                                arity,
+                                expr.arguments.len()
+                            ),
+                        );
+                    }
+                }
+                self.lint_expression(expr.callee);
+                for &argument in &expr.arguments {
+                    self.lint_expression(argument);
+                }
+            }
+            Expr::Grouping(expr) => self.lint_expression(expr.expression),
+            Expr::Unary(expr) => self.lint_expression(expr.right),
+            // reads the variable and then assigns it
+            Expr::Update(expr) => {
+                self.read(&expr.name);
+                if let Some(binding) = self.lookup(&expr.name.lexeme) {
+                    binding.arity = None;
+                }
+            }
+            Expr::Variable(expr) => self.read(&expr.name),
+            Expr::Literal(_) => (),
+        }
+    }
+
+    fn read(&mut self, name: &Token) {
+        let is_local = self.scopes.len() > 1;
+        let uninitialized = is_local
+            && self
+                .scopes
+                .last()
+                .unwrap()
+                .get(&name.lexeme)
+                .is_some_and(|binding| !binding.initialized);
+        if uninitialized {
+            self.warn(
+                Lint::SelfInitializer,
+                name.line,
+                format!("`{}` is read in its own initializer", name.lexeme),
+            );
+        }
+        if let Some(binding) = self.lookup(&name.lexeme) {
+            binding.used = true;
+        }
+    }
+
+    fn declare(&mut self, name: &Token, kind: BindingKind, arity: Option<usize>) {
+        let depth = self.scopes.len() - 1;
+        if depth > 0 {
+            let shadowed = self.scopes[..depth]
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(&name.lexeme))
+                .map(|binding| binding.line);
+            if let Some(line) = shadowed {
+                self.warn(
+                    Lint::Shadowing,
+                    name.line,
+                    format!("`{}` shadows the declaration on line {}", name.lexeme, line),
+                );
+            }
+        }
+
+        self.scopes[depth].insert(
+            name.lexeme.clone(),
+            Binding {
+                line: name.line,
+                initialized: kind != BindingKind::Variable,
+                kind,
+                used: false,
+                arity,
+            },
+        );
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+
+    // `print foo;` where foo is a function declared with `fun` and not reassigned since
+    fn function_value(&mut self, id: ExprId) -> Option<&'a Token> {
+        let ast = self.ast;
+        match &ast[id] {
+            Expr::Grouping(expr) => self.function_value(expr.expression),
+            Expr::Variable(expr) => self
+                .lookup(&expr.name.lexeme)
+                .filter(|binding| binding.kind == BindingKind::Function && binding.arity.is_some())
+                .map(|_| &expr.name),
+            _ => None,
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    // globals can be used by code we don't see (the REPL), so only locals are reported as unused
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        let mut unused: Vec<(String, Binding)> = scope
+            .into_iter()
+            .filter(|(name, binding)| !binding.used && !name.starts_with('_'))
+            .collect();
+        unused.sort_by_key(|(_, binding)| binding.line);
+
+        for (name, binding) in unused {
+            let what = match binding.kind {
+                BindingKind::Variable => "local variable",
+                BindingKind::Parameter => "parameter",
+                BindingKind::Function => "local function",
+            };
+            self.warn(
+                Lint::UnusedVariable,
+                binding.line,
+                format!("{} `{}` is never used", what, name),
+            );
+        }
+    }
+
+    fn warn(&mut self, lint: Lint, line: usize, message: String) {
+        if self.config.is_enabled(lint) {
+            self.warnings.push(Warning {
+                lint,
+                line,
+                message,
+            });
+        }
+    }
+}
+
+// Whether every path through the statement leaves the function
+fn always_returns(statement: &Stmt) -> bool {
+    match statement {
+        Stmt::Return(_) => true,
+        Stmt::Block(stmt) => stmt.statements.iter().any(always_returns),
+        Stmt::If(stmt) => match &stmt.else_branch {
+            Some(else_branch) => always_returns(&stmt.then_branch) && always_returns(else_branch),
+            None => false,
+        },
+        _ => false,
+    }
+}
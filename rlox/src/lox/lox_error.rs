@@ -1,4 +1,4 @@
-use std::usize;
+use std::fmt;
 
 use crate::frontend::lox_value::LoxValue;
 
@@ -9,28 +9,118 @@ pub enum LoxError {
     ScannerError(ParserError),
     Runtime(RuntimeError),
     Return(LoxValue),
+    // a value thrown with `throw` that is on its way to the closest `catch`
+    Throw(Exception),
+}
+
+// The text `report` writes, for the places that show errors somewhere else than stderr
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoxError::Interpreter(e) => e.fmt(f),
+            LoxError::ParserError(e) => e.fmt(f),
+            LoxError::ScannerError(e) => e.fmt(f),
+            LoxError::Runtime(e) => e.fmt(f),
+            // interpret turns a stray return into a runtime error, this is just a fallback
+            LoxError::Return(_) => write!(f, "{:?}", "Can't return from top-level code."),
+            LoxError::Throw(e) => e.fmt(f),
+        }
+    }
+}
+
+impl LoxError {
+    pub fn report(&self) {
+        eprintln!("{}", self);
+    }
+
+    // Exit codes follow sysexits.h like the book: 65 (EX_DATAERR) when the script doesn't
+    // compile, 70 (EX_SOFTWARE) when it fails while running
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LoxError::ScannerError(_) | LoxError::ParserError(_) => 65,
+            LoxError::Interpreter(_)
+            | LoxError::Runtime(_)
+            | LoxError::Return(_)
+            | LoxError::Throw(_) => 70,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Exception {
+    pub value: LoxValue,
+    // `(function, line)` of every call that was active at the throw, the innermost first
+    pub stack: Vec<(String, usize)>,
+}
+impl Exception {
+    // `Uncaught exception: value` followed by one `at function (line n)` per frame
+    pub fn stack_trace(&self) -> String {
+        let mut trace = format!("Uncaught exception: {}", self.value.as_str());
+        for (function, line) in &self.stack {
+            trace.push_str(&format!("\n    at {} (line {})", function, line));
+        }
+        trace
+    }
+
+    pub fn report(&self) {
+        eprintln!("{}", self);
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.stack_trace())
+    }
 }
 
 #[derive(Debug)]
 pub struct RuntimeError {
     message: String,
+    // filled in by the statement the error passed through first
+    line: Option<usize>,
 }
 impl RuntimeError {
     pub fn throw(message: String) -> Self {
         RuntimeError {
             message: message.to_string(),
+            line: None,
         }
     }
 
     pub fn arity_mismatch(expected: usize, found: usize) -> Self {
         Self {
             message: format!("Expect {expected} arguments, but got {found} arguments."),
+            line: None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    pub fn at_line(self, line: usize) -> Self {
+        Self {
+            line: self.line.or(Some(line)),
+            ..self
         }
     }
 
-    //TODO: get access to token so we can get the line where the error originated
     pub fn report(&self) {
-        eprintln!("{:?}", self.message);
+        eprintln!("{}", self);
+    }
+}
+
+// located like the errors of the parser and the scanner, once a statement filled in the line
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "[line {}] Error: {}", line, self.message),
+            None => write!(f, "Error: {}", self.message),
+        }
     }
 }
 
@@ -47,9 +137,23 @@ impl InterpreterError {
         }
     }
 
-    //Todo get access to token so we can get the line where the error originated
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
     pub fn report(&self) {
-        eprintln!("Location: {:?} error: {:?}", self.line, self.message);
+        eprintln!("{}", self);
+    }
+}
+
+//Todo get access to token so we can get the line where the error originated
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Location: {:?} error: {:?}", self.line, self.message)
     }
 }
 
@@ -76,20 +180,36 @@ impl ParserError {
         }
     }
 
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn location(&self) -> &Loc {
+        &self.location
+    }
+
     pub fn report(&self) {
+        eprintln!("{}", self);
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.location {
-            Loc::Lexeme(lexeme) => {
-                eprintln!(
-                    "[line {}, lexeme '{}'] Error: {}",
-                    self.line, lexeme, self.message
-                );
-            }
-            Loc::Pos(position) => {
-                eprintln!(
-                    "[line {}, position {}] Error: {}",
-                    self.line, position, self.message
-                );
-            }
+            Loc::Lexeme(lexeme) => write!(
+                f,
+                "[line {}, lexeme '{}'] Error: {}",
+                self.line, lexeme, self.message
+            ),
+            Loc::Pos(position) => write!(
+                f,
+                "[line {}, position {}] Error: {}",
+                self.line, position, self.message
+            ),
         }
     }
 }
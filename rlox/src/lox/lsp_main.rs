@@ -0,0 +1,9 @@
+use std::{io, process};
+
+use rulox::language_server::run_server;
+
+// language server for editors, speaks LSP over stdin/stdout
+fn main() -> Result<(), io::Error> {
+    let exit_code = run_server()?;
+    process::exit(exit_code);
+}
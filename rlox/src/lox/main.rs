@@ -1,22 +1,254 @@
 use std::env::args;
-use std::{io, process};
-use rulox::user_interface::{run_file, run_prompt};
+use std::path::Path;
+use std::{fs, io, process};
+use rulox::lint::{Lint, LintConfig};
+use rulox::tree_walker::arithmetic::NumericPolicy;
+use rulox::tree_walker::optimizer::OptLevel;
+use rulox::user_interface::{
+    dump_script, run_coverage, run_debugger, run_lint, run_profiler, run_prompt, run_script, Dump,
+};
+
+const USAGE: &str = "\
+Usage: lox [options] [script | -e <code> | -] [args...]
+       lox run <script> [args...]
+       lox lint [--enable <lint>] [--disable <lint>] <script>
+       lox debug <script>
+       lox --profile [--folded <path>] <script>
+       lox --coverage [--lcov <path>] <script>...
+
+Without a script lox starts the REPL. The script reads its arguments with argc() and argv(n).
+
+Options:
+  -e <code>                run the code given on the command line
+  -                        read the script from stdin
+  --tokens                 print the tokens instead of running the script
+  --ast                    print the syntax tree instead of running the script
+  --json                   print the tokens or the syntax tree as JSON
+  --opt-level <0|1|2>      fold constants (1) and drop dead code (2) before running (default: 0)
+  --numeric <ieee|error>   keep infinity and NaN results (ieee) or fail on them (default: ieee)
+  -V, --version            print the version
+  -h, --help               print this help
+
+Exit codes:
+  64  wrong usage            65  the script doesn't compile
+  66  the script is missing  70  the script failed while running
+  74  reading or writing failed";
+
+// where the script comes from
+enum Source {
+    File(String),
+    Inline(String),
+    Stdin,
+}
 
 // lox is a scripting language -> executes directly from source.
 // run code through a command-line interface (CLI) or by providing a path to a script file.
-fn main() -> Result<(), io::Error> {
-    let args: Vec<String> = args().collect();
+fn main() {
+    let args: Vec<String> = args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("lint") => lint_command(&args[1..]),
+        Some("debug") => {
+            if args.len() != 2 {
+                usage_error();
+            }
+            run_debugger(&args[1])
+        }
+        Some("--profile") => profile_command(&args[1..]),
+        Some("--coverage") => coverage_command(&args[1..]),
+        _ => run_command(&args),
+    };
+
+    // 66 (EX_NOINPUT) for a script that doesn't exist, 74 (EX_IOERR) when reading or writing failed
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        let code = if e.kind() == io::ErrorKind::NotFound {
+            66
+        } else {
+            74
+        };
+        process::exit(code);
+    }
+}
 
-    // we passed [0] program name, [1] path to file [x > 1] argumets to many
-    if args.len() > 2 {
-        eprintln!("Usage: rlox [script]");
-        process::exit(64);
-        // arg[0] is the programs name and arg[1] is the file_path we'll pass it
-    } else if args.len() == 2 {
-        run_file(&args[1])?;
-    } else {
-        run_prompt()?;
+// lox [options] [run] [script | -e <code> | -] [args...]
+fn run_command(args: &[String]) -> Result<(), io::Error> {
+    let mut dump: Option<Dump> = None;
+    let mut json = false;
+    let mut opt_level = OptLevel::default();
+    let mut numeric_policy = NumericPolicy::default();
+    let mut source: Option<Source> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "-V" | "--version" => {
+                println!("lox {}", env!("CARGO_PKG_VERSION"));
+                return Ok(());
+            }
+            "--tokens" => dump = Some(Dump::Tokens),
+            "--ast" => dump = Some(Dump::Ast),
+            "--json" => json = true,
+            "--backend" => match args.next().map(String::as_str) {
+                Some("tree") => (),
+                Some("vm") => {
+                    eprintln!("rlox only has the tree-walk interpreter, there is no vm backend");
+                    process::exit(64);
+                }
+                _ => usage_error(),
+            },
+            "--opt-level" => match args.next().map(|level| level.parse()) {
+                Some(Ok(level)) => opt_level = level,
+                _ => usage_error(),
+            },
+            "--numeric" => match args.next().map(|policy| policy.parse()) {
+                Some(Ok(policy)) => numeric_policy = policy,
+                _ => usage_error(),
+            },
+            "-e" => match args.next() {
+                Some(code) => {
+                    source = Some(Source::Inline(code.clone()));
+                    break;
+                }
+                None => usage_error(),
+            },
+            "-" => {
+                source = Some(Source::Stdin);
+                break;
+            }
+            "run" if source.is_none() => match args.next() {
+                Some(file_path) if !file_path.starts_with('-') || file_path == "-" => {
+                    source = Some(if file_path == "-" {
+                        Source::Stdin
+                    } else {
+                        Source::File(file_path.clone())
+                    });
+                    break;
+                }
+                _ => usage_error(),
+            },
+            option if option.starts_with('-') => usage_error(),
+            file_path => {
+                source = Some(Source::File(file_path.to_string()));
+                break;
+            }
+        }
+    }
+    // --json only changes how --tokens and --ast print
+    if json && dump.is_none() {
+        usage_error();
     }
+    // everything after the script belongs to the script
+    let script_args: Vec<String> = args.cloned().collect();
 
+    let script_path = match &source {
+        Some(Source::File(file_path)) => Some(Path::new(file_path)),
+        _ => None,
+    };
+    let contents = match &source {
+        Some(Source::File(file_path)) => fs::read_to_string(file_path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file_path, e)))?,
+        Some(Source::Inline(code)) => code.clone(),
+        Some(Source::Stdin) => io::read_to_string(io::stdin())?,
+        None if dump.is_none() => return run_prompt(),
+        None => usage_error(),
+    };
+
+    match dump {
+        Some(Dump::Tokens) if json => dump_script(&contents, Dump::TokensJson, opt_level),
+        Some(Dump::Ast) if json => dump_script(&contents, Dump::AstJson, opt_level),
+        Some(dump) => dump_script(&contents, dump, opt_level),
+        None => run_script(
+            &contents,
+            script_path,
+            script_args,
+            opt_level,
+            numeric_policy,
+        ),
+    }
     Ok(())
 }
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(64);
+}
+
+// lox lint [--enable <lint>] [--disable <lint>] script
+fn lint_command(args: &[String]) -> Result<(), io::Error> {
+    let mut config = LintConfig::default();
+    let mut file_path: Option<&str> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            flag @ ("--enable" | "--disable") => {
+                let lints = match args.next().map(String::as_str) {
+                    Some("all") => Lint::ALL.to_vec(),
+                    Some(name) => match Lint::from_name(name) {
+                        Some(lint) => vec![lint],
+                        None => lint_usage(),
+                    },
+                    None => lint_usage(),
+                };
+                for lint in lints {
+                    if flag == "--enable" {
+                        config.enable(lint);
+                    } else {
+                        config.disable(lint);
+                    }
+                }
+            }
+            path if file_path.is_none() && !path.starts_with("--") => file_path = Some(path),
+            _ => lint_usage(),
+        }
+    }
+
+    let Some(file_path) = file_path else {
+        lint_usage();
+    };
+    if run_lint(file_path, &config)? > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// lox --profile [--folded <path>] script
+fn profile_command(args: &[String]) -> Result<(), io::Error> {
+    let (folded_path, file_path) = match args {
+        [file_path] => (None, file_path),
+        [flag, folded_path, file_path] if flag == "--folded" => {
+            (Some(folded_path.as_str()), file_path)
+        }
+        _ => usage_error(),
+    };
+    run_profiler(file_path, folded_path)
+}
+
+// lox --coverage [--lcov <path>] script...
+fn coverage_command(args: &[String]) -> Result<(), io::Error> {
+    let (lcov_path, file_paths) = match args {
+        [flag, lcov_path, file_paths @ ..] if flag == "--lcov" => {
+            (Some(lcov_path.as_str()), file_paths)
+        }
+        file_paths => (None, file_paths),
+    };
+    if file_paths.is_empty() || file_paths.iter().any(|path| path.starts_with("--")) {
+        usage_error();
+    }
+    match run_coverage(file_paths, lcov_path)? {
+        0 => Ok(()),
+        code => process::exit(code),
+    }
+}
+
+fn lint_usage() -> ! {
+    let lints: Vec<&str> = Lint::ALL.iter().map(|lint| lint.name()).collect();
+    eprintln!("Usage: lox lint [--enable <lint>] [--disable <lint>] <script>");
+    eprintln!("Lints: all, {}", lints.join(", "));
+    process::exit(64);
+}
@@ -0,0 +1,229 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::frontend::lox_value::LoxValue;
+use crate::frontend::token::Token;
+use crate::frontend::token_type::TokenType;
+use crate::{InterpreterError, LoxError, RuntimeError};
+
+// What a float operation does when finite operands give infinity or NaN, e.g. `1 / 0` or
+// `0.0 % 0`, `--numeric` on the command line. Integer division by zero fails under both,
+// with the error the error policy raises for floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumericPolicy {
+    // the IEEE 754 result
+    #[default]
+    Ieee,
+    // a runtime error on the line of the operator
+    Error,
+}
+
+impl FromStr for NumericPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "ieee" => Ok(NumericPolicy::Ieee),
+            "error" => Ok(NumericPolicy::Error),
+            _ => Err(format!("Unknown numeric policy '{}'", policy)),
+        }
+    }
+}
+
+// A binary operator applied to two numbers, `None` when the operands aren't numbers or the
+// operator isn't defined for them. Integers stay integers as long as the result fits, `/` always
+// divides as floats and an integer next to a float is promoted to a float.
+pub fn binary(
+    operator: &Token,
+    left: &LoxValue,
+    right: &LoxValue,
+    policy: NumericPolicy,
+) -> Result<Option<LoxValue>, LoxError> {
+    let result = match (left, right) {
+        (LoxValue::Integer(a), LoxValue::Integer(b)) => integer(operator, *a, *b)?,
+        (LoxValue::Integer(_) | LoxValue::Float(_), LoxValue::Integer(_) | LoxValue::Float(_)) => {
+            float(&operator.token_type, to_float(left), to_float(right))
+        }
+        _ => None,
+    };
+    // infinity or NaN going in isn't the fault of this operator
+    if let Some(LoxValue::Float(number)) = result {
+        if policy == NumericPolicy::Error
+            && !number.is_finite()
+            && to_float(left).is_finite()
+            && to_float(right).is_finite()
+        {
+            return Err(not_finite(operator, left, right));
+        }
+    }
+    Ok(result)
+}
+
+// `-` in front of a number, the only integer without a negative counterpart overflows
+pub fn negate(operator: &Token, value: &LoxValue) -> Result<Option<LoxValue>, LoxError> {
+    match value {
+        LoxValue::Integer(number) => number
+            .checked_neg()
+            .map(|negated| Some(LoxValue::Integer(negated)))
+            .ok_or_else(|| error(operator, format!("Integer overflow in -{}.", number))),
+        LoxValue::Float(number) => Ok(Some(LoxValue::Float(-number))),
+        _ => Ok(None),
+    }
+}
+
+fn to_float(value: &LoxValue) -> f64 {
+    match value {
+        LoxValue::Integer(number) => *number as f64,
+        LoxValue::Float(number) => *number,
+        _ => f64::NAN,
+    }
+}
+
+fn integer(operator: &Token, a: i64, b: i64) -> Result<Option<LoxValue>, LoxError> {
+    let result = match operator.token_type {
+        TokenType::Plus => a.checked_add(b),
+        TokenType::Minus => a.checked_sub(b),
+        TokenType::Star => a.checked_mul(b),
+        // a negative exponent has no integer result
+        TokenType::StarStar if b < 0 => return Ok(float(&operator.token_type, a as f64, b as f64)),
+        TokenType::StarStar => u32::try_from(b).ok().and_then(|exp| a.checked_pow(exp)),
+        TokenType::Slash => return Ok(float(&operator.token_type, a as f64, b as f64)),
+        TokenType::TildeSlash | TokenType::Percent if b == 0 => {
+            return Err(division_by_zero(operator))
+        }
+        // both round towards negative infinity, so `a == (a ~/ b) * b + a % b` always holds
+        TokenType::TildeSlash => a.checked_div(b).map(|quotient| {
+            if a % b != 0 && (a < 0) != (b < 0) {
+                quotient - 1
+            } else {
+                quotient
+            }
+        }),
+        TokenType::Percent => {
+            // only i64::MIN % -1 wraps, and its remainder is 0 anyway
+            let remainder = a.wrapping_rem(b);
+            Some(if remainder != 0 && (remainder < 0) != (b < 0) {
+                remainder + b
+            } else {
+                remainder
+            })
+        }
+        _ => return Ok(compare(&operator.token_type, a.partial_cmp(&b))),
+    };
+    result
+        .map(|number| Some(LoxValue::Integer(number)))
+        .ok_or_else(|| {
+            error(
+                operator,
+                format!("Integer overflow in {} {} {}.", a, operator.lexeme, b),
+            )
+        })
+}
+
+fn float(operator: &TokenType, a: f64, b: f64) -> Option<LoxValue> {
+    let number = match operator {
+        TokenType::Plus => a + b,
+        TokenType::Minus => a - b,
+        TokenType::Star => a * b,
+        TokenType::StarStar => a.powf(b),
+        TokenType::Slash => a / b,
+        TokenType::TildeSlash => (a / b).floor(),
+        TokenType::Percent => a - b * (a / b).floor(),
+        _ => return compare(operator, a.partial_cmp(&b)),
+    };
+    Some(LoxValue::Float(number))
+}
+
+// NaN isn't ordered, every comparison with it is false except `!=`
+fn compare(operator: &TokenType, ordering: Option<Ordering>) -> Option<LoxValue> {
+    let result = match operator {
+        TokenType::Greater => ordering == Some(Ordering::Greater),
+        TokenType::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        TokenType::Less => ordering == Some(Ordering::Less),
+        TokenType::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        TokenType::EqualEqual => ordering == Some(Ordering::Equal),
+        TokenType::BangEqual => ordering != Some(Ordering::Equal),
+        _ => return None,
+    };
+    Some(LoxValue::Boolean(result))
+}
+
+pub fn is_bitwise(operator: &TokenType) -> bool {
+    matches!(
+        operator,
+        TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater
+    )
+}
+
+// `& | ^ << >>` only apply to integers, a float is an error even when it has no fraction
+pub fn bitwise(operator: &Token, left: &LoxValue, right: &LoxValue) -> Result<LoxValue, LoxError> {
+    let (LoxValue::Integer(a), LoxValue::Integer(b)) = (left, right) else {
+        return Err(error(
+            operator,
+            format!(
+                "Operands of '{}' must be integers, got {} and {}.",
+                operator.lexeme, left, right
+            ),
+        ));
+    };
+    let result = match operator.token_type {
+        TokenType::Ampersand => a & b,
+        TokenType::Pipe => a | b,
+        TokenType::Caret => a ^ b,
+        // bits shifted out on the left are dropped, `>>` keeps the sign
+        TokenType::LessLess | TokenType::GreaterGreater => {
+            let shift = u32::try_from(*b).ok().filter(|shift| *shift < i64::BITS);
+            match (shift, &operator.token_type) {
+                (Some(shift), TokenType::LessLess) => a << shift,
+                (Some(shift), _) => a >> shift,
+                (None, _) => {
+                    return Err(error(
+                        operator,
+                        format!("Shift amount must be between 0 and 63, got {}.", b),
+                    ))
+                }
+            }
+        }
+        _ => unreachable!("not a bitwise operator: {:?}", operator.token_type),
+    };
+    Ok(LoxValue::Integer(result))
+}
+
+// `~` flips every bit of an integer
+pub fn complement(operator: &Token, value: &LoxValue) -> Result<LoxValue, LoxError> {
+    match value {
+        LoxValue::Integer(number) => Ok(LoxValue::Integer(!number)),
+        _ => Err(error(
+            operator,
+            format!("Operand of '~' must be an integer, got {}.", value),
+        )),
+    }
+}
+
+fn not_finite(operator: &Token, left: &LoxValue, right: &LoxValue) -> LoxError {
+    let division = matches!(
+        operator.token_type,
+        TokenType::Slash | TokenType::TildeSlash | TokenType::Percent
+    );
+    if division && to_float(right) == 0.0 {
+        return division_by_zero(operator);
+    }
+    let message = format!(
+        "{} {} {} is not a finite number.",
+        left, operator.lexeme, right
+    );
+    LoxError::Runtime(RuntimeError::throw(message).at_line(operator.line))
+}
+
+// Integers and floats under the error policy fail the same way, so one `catch` handles both
+fn division_by_zero(operator: &Token) -> LoxError {
+    LoxError::Runtime(RuntimeError::throw("Division by zero.".to_string()).at_line(operator.line))
+}
+
+fn error(operator: &Token, message: String) -> LoxError {
+    LoxError::Interpreter(InterpreterError::throw(operator.line, message))
+}
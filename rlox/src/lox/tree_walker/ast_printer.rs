@@ -0,0 +1,367 @@
+use std::fmt::Write;
+
+use crate::frontend::lox_value::LoxValue;
+
+use super::parser::{Ast, Expr, ExprId, FunctionDecl, Program, Stmt};
+
+// The book's parenthesized form, one statement per line:
+// `(fun add(a b) (return (+ a b)))`
+pub fn to_sexpr(program: &Program) -> String {
+    program
+        .statements
+        .iter()
+        .map(|stmt| stmt_to_sexpr(&program.ast, stmt))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn stmt_to_sexpr(ast: &Ast, statement: &Stmt) -> String {
+    match statement {
+        Stmt::Block(stmt) => parenthesize(
+            "block",
+            stmt.statements.iter().map(|stmt| stmt_to_sexpr(ast, stmt)),
+        ),
+        Stmt::Expression(stmt) => parenthesize(";", [expr_to_sexpr(ast, stmt.expression)]),
+        Stmt::Function(fun) => function_to_sexpr(ast, fun),
+        Stmt::If(stmt) => match &stmt.else_branch {
+            Some(else_branch) => parenthesize(
+                "if-else",
+                [
+                    expr_to_sexpr(ast, stmt.condition),
+                    stmt_to_sexpr(ast, &stmt.then_branch),
+                    stmt_to_sexpr(ast, else_branch),
+                ],
+            ),
+            None => parenthesize(
+                "if",
+                [
+                    expr_to_sexpr(ast, stmt.condition),
+                    stmt_to_sexpr(ast, &stmt.then_branch),
+                ],
+            ),
+        },
+        Stmt::Print(stmt) => parenthesize("print", [expr_to_sexpr(ast, stmt.expression)]),
+        Stmt::Return(stmt) => parenthesize(
+            "return",
+            stmt.value.iter().map(|&id| expr_to_sexpr(ast, id)),
+        ),
+        Stmt::Var(stmt) => match &stmt.initializer {
+            Some(initializer) => format!(
+                "(var {} = {})",
+                stmt.name.lexeme,
+                expr_to_sexpr(ast, *initializer)
+            ),
+            None => format!("(var {})", stmt.name.lexeme),
+        },
+        Stmt::While(stmt) => parenthesize(
+            "while",
+            [
+                expr_to_sexpr(ast, stmt.condition),
+                stmt_to_sexpr(ast, &stmt.body),
+            ],
+        ),
+        Stmt::Import(stmt) => match &stmt.names {
+            Some(names) => format!(
+                "(import {} from {:?})",
+                names
+                    .iter()
+                    .map(|name| name.lexeme.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" "),
+                stmt.path.lexeme
+            ),
+            None => format!("(import {:?})", stmt.path.lexeme),
+        },
+        Stmt::Throw(stmt) => parenthesize("throw", [expr_to_sexpr(ast, stmt.value)]),
+        Stmt::Try(stmt) => {
+            let mut parts = vec![parenthesize(
+                "block",
+                stmt.body.iter().map(|stmt| stmt_to_sexpr(ast, stmt)),
+            )];
+            if let Some(catch) = &stmt.catch {
+                parts.push(parenthesize(
+                    &format!("catch {}", catch.name.lexeme),
+                    catch.body.iter().map(|stmt| stmt_to_sexpr(ast, stmt)),
+                ));
+            }
+            if let Some(finally) = &stmt.finally {
+                parts.push(parenthesize(
+                    "finally",
+                    finally.iter().map(|stmt| stmt_to_sexpr(ast, stmt)),
+                ));
+            }
+            parenthesize("try", parts)
+        }
+    }
+}
+
+fn function_to_sexpr(ast: &Ast, fun: &FunctionDecl) -> String {
+    let parameters: Vec<&str> = fun
+        .parameters
+        .iter()
+        .map(|parameter| parameter.lexeme.as_str())
+        .collect();
+    parenthesize(
+        &format!("fun {}({})", fun.name.lexeme, parameters.join(" ")),
+        fun.body.iter().map(|stmt| stmt_to_sexpr(ast, stmt)),
+    )
+}
+
+pub fn expr_to_sexpr(ast: &Ast, id: ExprId) -> String {
+    match &ast[id] {
+        Expr::Assign(expr) => format!(
+            "(= {} {})",
+            expr.name.lexeme,
+            expr_to_sexpr(ast, expr.value)
+        ),
+        Expr::Binary(expr) => parenthesize(
+            &expr.operator.lexeme,
+            [
+                expr_to_sexpr(ast, expr.left),
+                expr_to_sexpr(ast, expr.right),
+            ],
+        ),
+        Expr::Call(expr) => parenthesize(
+            "call",
+            std::iter::once(expr_to_sexpr(ast, expr.callee))
+                .chain(expr.arguments.iter().map(|&id| expr_to_sexpr(ast, id))),
+        ),
+        Expr::Comma(expr) => parenthesize(
+            &expr.operator.lexeme,
+            [
+                expr_to_sexpr(ast, expr.left),
+                expr_to_sexpr(ast, expr.right),
+            ],
+        ),
+        Expr::Conditional(expr) => parenthesize(
+            "?:",
+            [
+                expr_to_sexpr(ast, expr.condition),
+                expr_to_sexpr(ast, expr.then_branch),
+                expr_to_sexpr(ast, expr.else_branch),
+            ],
+        ),
+        Expr::Grouping(expr) => parenthesize("group", [expr_to_sexpr(ast, expr.expression)]),
+        Expr::Literal(expr) => literal(&expr.value),
+        Expr::Logical(expr) => parenthesize(
+            &expr.operator.lexeme,
+            [
+                expr_to_sexpr(ast, expr.left),
+                expr_to_sexpr(ast, expr.right),
+            ],
+        ),
+        Expr::Unary(expr) => parenthesize(&expr.operator.lexeme, [expr_to_sexpr(ast, expr.right)]),
+        Expr::Update(expr) if expr.prefix => {
+            parenthesize(&expr.operator.lexeme, [expr.name.lexeme.clone()])
+        }
+        Expr::Update(expr) => format!("({} {})", expr.name.lexeme, expr.operator.lexeme),
+        Expr::Variable(expr) => expr.name.lexeme.clone(),
+    }
+}
+
+fn parenthesize(name: &str, parts: impl IntoIterator<Item = String>) -> String {
+    let mut sexpr = format!("({}", name);
+    for part in parts {
+        sexpr.push(' ');
+        sexpr.push_str(&part);
+    }
+    sexpr.push(')');
+    sexpr
+}
+
+fn literal(value: &LoxValue) -> String {
+    match value {
+        LoxValue::String(s) => format!("{:?}", s),
+        _ => value.as_str(),
+    }
+}
+
+// Indented tree of the program, every node on its own line:
+// Program
+// └── Print
+//     └── Binary +
+//         ├── Literal 1
+//         └── Literal 2
+pub fn to_tree(program: &Program) -> String {
+    let ast = &program.ast;
+    let statements = &program.statements;
+    let program = Node::new(
+        "Program",
+        statements.iter().map(|stmt| stmt_node(ast, stmt)).collect(),
+    );
+    let mut tree = String::new();
+    program.render(&mut tree, "", "");
+    tree
+}
+
+pub fn expr_to_tree(ast: &Ast, id: ExprId) -> String {
+    let mut tree = String::new();
+    expr_node(ast, id).render(&mut tree, "", "");
+    tree
+}
+
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: &str, children: Vec<Node>) -> Self {
+        Node {
+            label: label.to_string(),
+            children,
+        }
+    }
+
+    fn leaf(label: &str) -> Self {
+        Self::new(label, Vec::new())
+    }
+
+    // `prefix` goes in front of this node, `indent` in front of the lines of its children
+    fn render(&self, tree: &mut String, prefix: &str, indent: &str) {
+        let _ = writeln!(tree, "{}{}", prefix, self.label);
+        for (index, child) in self.children.iter().enumerate() {
+            let (branch, continuation) = if index + 1 == self.children.len() {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            child.render(
+                tree,
+                &format!("{}{}", indent, branch),
+                &format!("{}{}", indent, continuation),
+            );
+        }
+    }
+}
+
+fn stmt_node(ast: &Ast, statement: &Stmt) -> Node {
+    match statement {
+        Stmt::Block(stmt) => Node::new(
+            "Block",
+            stmt.statements
+                .iter()
+                .map(|stmt| stmt_node(ast, stmt))
+                .collect(),
+        ),
+        Stmt::Expression(stmt) => Node::new("Expression", vec![expr_node(ast, stmt.expression)]),
+        Stmt::Function(fun) => {
+            let parameters: Vec<&str> = fun
+                .parameters
+                .iter()
+                .map(|parameter| parameter.lexeme.as_str())
+                .collect();
+            Node::new(
+                &format!("Function {}({})", fun.name.lexeme, parameters.join(", ")),
+                fun.body.iter().map(|stmt| stmt_node(ast, stmt)).collect(),
+            )
+        }
+        Stmt::If(stmt) => {
+            let mut children = vec![
+                Node::new("Condition", vec![expr_node(ast, stmt.condition)]),
+                Node::new("Then", vec![stmt_node(ast, &stmt.then_branch)]),
+            ];
+            if let Some(else_branch) = &stmt.else_branch {
+                children.push(Node::new("Else", vec![stmt_node(ast, else_branch)]));
+            }
+            Node::new("If", children)
+        }
+        Stmt::Print(stmt) => Node::new("Print", vec![expr_node(ast, stmt.expression)]),
+        Stmt::Return(stmt) => Node::new(
+            "Return",
+            stmt.value.iter().map(|&id| expr_node(ast, id)).collect(),
+        ),
+        Stmt::Var(stmt) => Node::new(
+            &format!("Var {}", stmt.name.lexeme),
+            stmt.initializer
+                .iter()
+                .map(|&id| expr_node(ast, id))
+                .collect(),
+        ),
+        Stmt::While(stmt) => Node::new(
+            "While",
+            vec![
+                Node::new("Condition", vec![expr_node(ast, stmt.condition)]),
+                Node::new("Body", vec![stmt_node(ast, &stmt.body)]),
+            ],
+        ),
+        Stmt::Import(stmt) => Node::new(
+            &format!("Import {:?}", stmt.path.lexeme),
+            stmt.names
+                .iter()
+                .flatten()
+                .map(|name| Node::leaf(&format!("Name {}", name.lexeme)))
+                .collect(),
+        ),
+        Stmt::Throw(stmt) => Node::new("Throw", vec![expr_node(ast, stmt.value)]),
+        Stmt::Try(stmt) => {
+            let mut children = vec![Node::new(
+                "Body",
+                stmt.body.iter().map(|stmt| stmt_node(ast, stmt)).collect(),
+            )];
+            if let Some(catch) = &stmt.catch {
+                children.push(Node::new(
+                    &format!("Catch {}", catch.name.lexeme),
+                    catch.body.iter().map(|stmt| stmt_node(ast, stmt)).collect(),
+                ));
+            }
+            if let Some(finally) = &stmt.finally {
+                children.push(Node::new(
+                    "Finally",
+                    finally.iter().map(|stmt| stmt_node(ast, stmt)).collect(),
+                ));
+            }
+            Node::new("Try", children)
+        }
+    }
+}
+
+fn expr_node(ast: &Ast, id: ExprId) -> Node {
+    match &ast[id] {
+        Expr::Assign(expr) => Node::new(
+            &format!("Assign {}", expr.name.lexeme),
+            vec![expr_node(ast, expr.value)],
+        ),
+        Expr::Binary(expr) => Node::new(
+            &format!("Binary {}", expr.operator.lexeme),
+            vec![expr_node(ast, expr.left), expr_node(ast, expr.right)],
+        ),
+        Expr::Call(expr) => Node::new(
+            "Call",
+            std::iter::once(expr_node(ast, expr.callee))
+                .chain(expr.arguments.iter().map(|&id| expr_node(ast, id)))
+                .collect(),
+        ),
+        Expr::Comma(expr) => Node::new(
+            "Comma",
+            vec![expr_node(ast, expr.left), expr_node(ast, expr.right)],
+        ),
+        Expr::Conditional(expr) => Node::new(
+            "Conditional",
+            vec![
+                expr_node(ast, expr.condition),
+                expr_node(ast, expr.then_branch),
+                expr_node(ast, expr.else_branch),
+            ],
+        ),
+        Expr::Grouping(expr) => Node::new("Grouping", vec![expr_node(ast, expr.expression)]),
+        Expr::Literal(expr) => Node::leaf(&format!("Literal {}", literal(&expr.value))),
+        Expr::Logical(expr) => Node::new(
+            &format!("Logical {}", expr.operator.lexeme),
+            vec![expr_node(ast, expr.left), expr_node(ast, expr.right)],
+        ),
+        Expr::Unary(expr) => Node::new(
+            &format!("Unary {}", expr.operator.lexeme),
+            vec![expr_node(ast, expr.right)],
+        ),
+        Expr::Update(expr) if expr.prefix => Node::leaf(&format!(
+            "Update {}{}",
+            expr.operator.lexeme, expr.name.lexeme
+        )),
+        Expr::Update(expr) => Node::leaf(&format!(
+            "Update {}{}",
+            expr.name.lexeme, expr.operator.lexeme
+        )),
+        Expr::Variable(expr) => Node::leaf(&format!("Variable {}", expr.name.lexeme)),
+    }
+}
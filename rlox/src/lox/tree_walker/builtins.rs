@@ -1,7 +1,11 @@
+use std::rc::Rc;
+
 use chrono::offset::Utc;
 
 use crate::frontend::{lox_callable::LoxCallable, lox_value::LoxValue};
+use crate::{LoxError, RuntimeError};
 
+use super::arithmetic::NumericPolicy;
 use super::interpreter;
 
 #[derive(Debug)]
@@ -17,10 +21,206 @@ impl LoxCallable for Clock {
         _: &mut interpreter::Interpreter,
         _: Vec<LoxValue>,
     ) -> Result<LoxValue, crate::LoxError> {
-        return Ok(LoxValue::Integer(Utc::now().timestamp() as f64));
+        Ok(LoxValue::Integer(Utc::now().timestamp()))
     }
 
     fn name(&self) -> &str {
         "native function"
     }
 }
+
+// argc() -> number of arguments passed to the script after its name
+#[derive(Debug)]
+pub struct Argc {
+    pub count: usize,
+}
+
+impl LoxCallable for Argc {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(
+        &self,
+        _: &mut interpreter::Interpreter,
+        _: Vec<LoxValue>,
+    ) -> Result<LoxValue, crate::LoxError> {
+        Ok(LoxValue::Integer(self.count as i64))
+    }
+
+    fn name(&self) -> &str {
+        "argc"
+    }
+}
+
+// argv(n) -> the nth script argument as a string, nil when there is no such argument
+#[derive(Debug)]
+pub struct Argv {
+    pub args: Vec<String>,
+}
+
+impl LoxCallable for Argv {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _: &mut interpreter::Interpreter,
+        args: Vec<LoxValue>,
+    ) -> Result<LoxValue, crate::LoxError> {
+        let value = match args.first() {
+            Some(LoxValue::Integer(index)) => usize::try_from(*index)
+                .ok()
+                .and_then(|index| self.args.get(index))
+                .map_or(LoxValue::Nil, |arg| LoxValue::String(arg.clone())),
+            _ => LoxValue::Nil,
+        };
+        Ok(value)
+    }
+
+    fn name(&self) -> &str {
+        "argv"
+    }
+}
+
+// errorMessage(e) -> message of an error caught from the interpreter, any other caught value as
+// a string
+#[derive(Debug)]
+pub struct ErrorMessage {}
+
+impl LoxCallable for ErrorMessage {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _: &mut interpreter::Interpreter,
+        args: Vec<LoxValue>,
+    ) -> Result<LoxValue, crate::LoxError> {
+        Ok(LoxValue::String(
+            args.first().map_or_else(String::new, LoxValue::as_str),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "errorMessage"
+    }
+}
+
+// errorLine(e) -> line the interpreter failed on, nil for values thrown by the script
+#[derive(Debug)]
+pub struct ErrorLine {}
+
+impl LoxCallable for ErrorLine {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _: &mut interpreter::Interpreter,
+        args: Vec<LoxValue>,
+    ) -> Result<LoxValue, crate::LoxError> {
+        let line = match args.first() {
+            Some(LoxValue::Error(error)) => LoxValue::Integer(error.line as i64),
+            _ => LoxValue::Nil,
+        };
+        Ok(line)
+    }
+
+    fn name(&self) -> &str {
+        "errorLine"
+    }
+}
+
+// str(value) -> the value as a string, the same text `print` writes. Interpolated strings call it
+// for every `${}`.
+#[derive(Debug)]
+pub struct Str {}
+
+impl LoxCallable for Str {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        _: &mut interpreter::Interpreter,
+        args: Vec<LoxValue>,
+    ) -> Result<LoxValue, crate::LoxError> {
+        Ok(LoxValue::String(
+            args.first().map_or_else(String::new, LoxValue::as_str),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "str"
+    }
+}
+
+// sqrt(x), log(x), exp(x) -> the function of a number, always a float. Like the operators, a
+// finite argument that gives infinity or NaN, e.g. `sqrt(-1)` or `log(0)`, is a runtime error
+// under the error numeric policy.
+#[derive(Debug)]
+pub struct Math {
+    pub name: &'static str,
+    pub function: fn(f64) -> f64,
+}
+
+impl LoxCallable for Math {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut interpreter::Interpreter,
+        args: Vec<LoxValue>,
+    ) -> Result<LoxValue, crate::LoxError> {
+        let number = match args.first() {
+            Some(LoxValue::Integer(number)) => *number as f64,
+            Some(LoxValue::Float(number)) => *number,
+            other => {
+                let value = other.map_or_else(String::new, LoxValue::as_str);
+                return Err(LoxError::Runtime(RuntimeError::throw(format!(
+                    "Argument of '{}' must be a number, got {}.",
+                    self.name, value
+                ))));
+            }
+        };
+        let result = (self.function)(number);
+        if interpreter.numeric_policy() == NumericPolicy::Error
+            && number.is_finite()
+            && !result.is_finite()
+        {
+            return Err(LoxError::Runtime(RuntimeError::throw(format!(
+                "{}({}) is not a finite number.",
+                self.name, args[0]
+            ))));
+        }
+        Ok(LoxValue::Float(result))
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+fn math(name: &'static str, function: fn(f64) -> f64) -> Option<LoxValue> {
+    Some(LoxValue::Function(Rc::new(Math { name, function })))
+}
+
+// Natives every script can call, a variable of the same name shadows them
+pub fn native(name: &str) -> Option<LoxValue> {
+    match name {
+        "str" => Some(LoxValue::Function(Rc::new(Str {}))),
+        "errorMessage" => Some(LoxValue::Function(Rc::new(ErrorMessage {}))),
+        "errorLine" => Some(LoxValue::Function(Rc::new(ErrorLine {}))),
+        "sqrt" => math("sqrt", f64::sqrt),
+        "log" => math("log", f64::ln),
+        "exp" => math("exp", f64::exp),
+        _ => None,
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::parser::Stmt;
+
+// Which statements and branches of a script ran. Every statement is registered up front so the
+// ones that never ran show up with a count of zero.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    // line -> how often a statement on it was executed
+    pub lines: BTreeMap<usize, u64>,
+    // line of an `if` or `while` -> [taken, not taken]. For an `if` that is then and else (also
+    // when there is no else), for a `while` the condition being true or false. Two branching
+    // statements on one line share their counters.
+    pub branches: BTreeMap<usize, [u64; 2]>,
+    // coverage of every module the script imported, by the module's file
+    pub modules: BTreeMap<PathBuf, Coverage>,
+}
+
+impl Coverage {
+    pub fn new(statements: &[Stmt]) -> Self {
+        let mut coverage = Coverage::default();
+        coverage.register(statements);
+        coverage
+    }
+
+    // Register the statements of a module the first time it is imported
+    pub fn add_module(&mut self, path: &Path, statements: &[Stmt]) {
+        if !self.modules.contains_key(path) {
+            self.modules
+                .insert(path.to_path_buf(), Coverage::new(statements));
+        }
+    }
+
+    // Counters for the lines of `module`, `None` is the script itself
+    pub fn file(&mut self, module: Option<&Path>) -> &mut Coverage {
+        match module {
+            None => self,
+            Some(module) => {
+                if !self.modules.contains_key(module) {
+                    self.modules
+                        .insert(module.to_path_buf(), Coverage::default());
+                }
+                self.modules.get_mut(module).unwrap()
+            }
+        }
+    }
+
+    fn register(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.register_statement(statement);
+        }
+    }
+
+    fn register_statement(&mut self, statement: &Stmt) {
+        // a block only opens a scope, its statements are counted on their own lines
+        if !matches!(statement, Stmt::Block(_)) {
+            self.lines.entry(statement.line()).or_default();
+        }
+        match statement {
+            Stmt::Block(stmt) => self.register(&stmt.statements),
+            Stmt::Function(fun) => self.register(&fun.body),
+            Stmt::If(stmt) => {
+                self.branches.entry(stmt.line).or_default();
+                self.register_statement(&stmt.then_branch);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.register_statement(else_branch);
+                }
+            }
+            Stmt::While(stmt) => {
+                self.branches.entry(stmt.line).or_default();
+                self.register_statement(&stmt.body);
+            }
+            Stmt::Try(stmt) => {
+                self.register(&stmt.body);
+                if let Some(catch) = &stmt.catch {
+                    self.register(&catch.body);
+                }
+                if let Some(finally) = &stmt.finally {
+                    self.register(finally);
+                }
+            }
+            Stmt::Expression(_)
+            | Stmt::Var(_)
+            | Stmt::Print(_)
+            | Stmt::Return(_)
+            | Stmt::Import(_)
+            | Stmt::Throw(_) => (),
+        }
+    }
+
+    pub fn hit_line(&mut self, line: usize) {
+        *self.lines.entry(line).or_default() += 1;
+    }
+
+    pub fn hit_branch(&mut self, line: usize, taken: bool) {
+        let counters = self.branches.entry(line).or_default();
+        counters[usize::from(!taken)] += 1;
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&hits| hits > 0).count()
+    }
+
+    pub fn branches_found(&self) -> usize {
+        self.branches.len() * 2
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.branches
+            .values()
+            .flatten()
+            .filter(|&&hits| hits > 0)
+            .count()
+    }
+
+    // One LCOV record for the script, see `geninfo(1)` for the format
+    pub fn lcov(&self, source_file: &str) -> String {
+        let mut record = String::new();
+        let _ = writeln!(record, "TN:");
+        let _ = writeln!(record, "SF:{}", source_file);
+        for (line, counters) in &self.branches {
+            let reached = self.lines.get(line).is_some_and(|&hits| hits > 0);
+            for (branch, hits) in counters.iter().enumerate() {
+                // `-` tells the tools the branch was never even evaluated
+                let taken = if reached {
+                    hits.to_string()
+                } else {
+                    "-".to_string()
+                };
+                let _ = writeln!(record, "BRDA:{},0,{},{}", line, branch, taken);
+            }
+        }
+        let _ = writeln!(record, "BRF:{}", self.branches_found());
+        let _ = writeln!(record, "BRH:{}", self.branches_hit());
+        for (line, hits) in &self.lines {
+            let _ = writeln!(record, "DA:{},{}", line, hits);
+        }
+        let _ = writeln!(record, "LF:{}", self.lines.len());
+        let _ = writeln!(record, "LH:{}", self.lines_hit());
+        let _ = writeln!(record, "end_of_record");
+        record
+    }
+
+    // `path: lines 9/10 (90.0%), branches 3/4 (75.0%)` followed by the lines that never ran
+    pub fn summary(&self, source_file: &str) -> String {
+        let mut summary = format!(
+            "{}: lines {}/{} ({:.1}%), branches {}/{} ({:.1}%)",
+            source_file,
+            self.lines_hit(),
+            self.lines.len(),
+            percent(self.lines_hit(), self.lines.len()),
+            self.branches_hit(),
+            self.branches_found(),
+            percent(self.branches_hit(), self.branches_found())
+        );
+        let missed: Vec<String> = self
+            .lines
+            .iter()
+            .filter(|(_, &hits)| hits == 0)
+            .map(|(line, _)| line.to_string())
+            .collect();
+        if !missed.is_empty() {
+            let _ = write!(summary, "\n  not executed: {}", missed.join(", "));
+        }
+        summary
+    }
+}
+
+fn percent(hit: usize, found: usize) -> f64 {
+    if found == 0 {
+        100.0
+    } else {
+        hit as f64 * 100.0 / found as f64
+    }
+}
@@ -0,0 +1,148 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::environment::Environment;
+use super::interpreter::Interpreter;
+
+// How execution continues after the debugger paused
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepMode {
+    Continue,
+    // pause on the next statement, also inside a called function
+    StepIn,
+    // pause on the next statement of the current function or its caller
+    StepOver,
+    // pause once the current function returned to its caller
+    StepOut,
+    // abort the script
+    Terminate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseReason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+// Frontends (terminal prompt, debug adapter) implement this to drive a paused interpreter
+pub trait Debugger {
+    // `module` is the file of an imported module, `None` for the script that is debugged
+    fn is_breakpoint(&self, module: Option<&Path>, line: usize) -> bool;
+
+    // Called before a statement is executed when the interpreter pauses. The interpreter can be
+    // inspected and used to evaluate expressions, the returned mode decides where to stop next.
+    fn paused(
+        &mut self,
+        interpreter: &mut Interpreter,
+        line: usize,
+        reason: PauseReason,
+    ) -> StepMode;
+}
+
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    // unique per call, two calls of the same function at the same depth are different frames
+    pub id: usize,
+    pub function: String,
+    // line of the statement that is being executed in this frame
+    pub line: usize,
+    // module the line is in, `None` for the script
+    pub module: Option<Rc<Path>>,
+    // environment of the frame at the moment it called the next frame, the innermost frame uses
+    // the current environment of the interpreter instead
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+impl CallFrame {
+    pub fn new(
+        id: usize,
+        function: &str,
+        line: usize,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Self {
+        CallFrame {
+            id,
+            function: function.to_string(),
+            line,
+            module: None,
+            environment,
+        }
+    }
+}
+
+// frame id, the module (`None` for the script) and the line of a statement
+type Position = (usize, Option<Rc<Path>>, usize);
+
+pub struct DebugSession {
+    debugger: Box<dyn Debugger>,
+    mode: StepMode,
+    // call depth when the step was requested
+    depth: usize,
+    // frame id, module and line of the previous statement, so a breakpoint fires once per visit
+    // of a line
+    last_position: Option<Position>,
+    started: bool,
+}
+
+impl DebugSession {
+    pub fn new(debugger: Box<dyn Debugger>, stop_on_entry: bool) -> Self {
+        DebugSession {
+            debugger,
+            mode: if stop_on_entry {
+                StepMode::StepIn
+            } else {
+                StepMode::Continue
+            },
+            depth: 0,
+            last_position: None,
+            started: false,
+        }
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.mode == StepMode::Terminate
+    }
+
+    fn should_pause(&self, depth: usize, position: &Position) -> Option<PauseReason> {
+        let step_done = match self.mode {
+            StepMode::StepIn => true,
+            StepMode::StepOver => depth <= self.depth,
+            StepMode::StepOut => depth < self.depth,
+            StepMode::Continue | StepMode::Terminate => false,
+        };
+        if step_done {
+            return Some(if self.started {
+                PauseReason::Step
+            } else {
+                PauseReason::Entry
+            });
+        }
+
+        if self.mode != StepMode::Terminate
+            && self.debugger.is_breakpoint(position.1.as_deref(), position.2)
+            && self.last_position.as_ref() != Some(position)
+        {
+            return Some(PauseReason::Breakpoint);
+        }
+        None
+    }
+
+    // Runs before every statement, hands control to the debugger when we have to pause
+    pub fn on_statement(&mut self, interpreter: &mut Interpreter, line: usize) {
+        let stack = interpreter.call_stack();
+        let depth = stack.len();
+        let position = match stack.last() {
+            Some(frame) => (frame.id, frame.module.clone(), line),
+            None => (0, None, line),
+        };
+
+        if let Some(reason) = self.should_pause(depth, &position) {
+            self.mode = self.debugger.paused(interpreter, line, reason);
+            self.depth = depth;
+        }
+        self.started = true;
+        self.last_position = Some(position);
+    }
+}
@@ -3,13 +3,25 @@ use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::frontend::lox_value::LoxValue;
+use crate::frontend::symbol::Symbol;
 use crate::frontend::token::Token;
 use crate::{LoxError, RuntimeError};
 
+use super::resolver::Slot;
+
+// The globals of the script or of a module are looked up by name. Blocks and calls keep their
+// locals in slots, in the order they were declared, which is the index the resolver gave them.
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub parent_env: Option<Rc<RefCell<Environment>>>,
-    variables: HashMap<String, LoxValue>,
+    variables: HashMap<Symbol, LoxValue>,
+    slots: Vec<(Symbol, LoxValue)>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Environment {
@@ -17,27 +29,68 @@ impl Environment {
         Environment {
             parent_env: None,
             variables: HashMap::new(),
+            slots: Vec::new(),
         }
     }
 }
 
 impl Environment {
     pub fn new_inner_environment(parent_environment: Rc<RefCell<Environment>>) -> Self {
-        let env = Environment {
+        Environment {
             parent_env: Some(parent_environment),
             variables: HashMap::new(),
-        };
-        env
+            slots: Vec::new(),
+        }
     }
 
-    pub fn define(&mut self, name: &str, value: LoxValue) {
-        self.variables.insert(name.to_string(), value);
+    pub fn define(&mut self, name: impl Into<Symbol>, value: LoxValue) {
+        let name = name.into();
+        if self.parent_env.is_none() {
+            self.variables.insert(name, value);
+        } else if let Some(variable) = self.variable_mut(&name) {
+            *variable = value;
+        } else {
+            self.slots.push((name, value));
+        }
+    }
+
+    // Variables of this scope only, sorted by name so dumps are stable
+    pub fn bindings(&self) -> Vec<(String, LoxValue)> {
+        let mut bindings: Vec<(String, LoxValue)> = self
+            .variables
+            .iter()
+            .chain(self.slots.iter().map(|(name, value)| (name, value)))
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        bindings.sort_by(|(left, _), (right, _)| left.cmp(right));
+        bindings
+    }
+
+    fn variable(&self, name: &Symbol) -> Option<&LoxValue> {
+        match self.parent_env {
+            None => self.variables.get(name),
+            Some(_) => self
+                .slots
+                .iter()
+                .find(|(slot, _)| slot == name)
+                .map(|(_, value)| value),
+        }
+    }
+
+    fn variable_mut(&mut self, name: &Symbol) -> Option<&mut LoxValue> {
+        match self.parent_env {
+            None => self.variables.get_mut(name),
+            Some(_) => self
+                .slots
+                .iter_mut()
+                .find(|(slot, _)| slot == name)
+                .map(|(_, value)| value),
+        }
     }
 
     // If the variable isn't found in this environment, we simply try the enclosing one
     pub fn get_literal(&self, name: &Token) -> Result<LoxValue, LoxError> {
-        let key = &name.lexeme;
-        if let Some(value) = self.variables.get(key) {
+        if let Some(value) = self.variable(&name.symbol()) {
             Ok(value.clone())
         } else {
             self.parent_env.as_ref().map_or_else(
@@ -54,9 +107,8 @@ impl Environment {
 
     // We get the current key and reassign a new value to it
     pub fn assign(&mut self, name: &Token, value: &LoxValue) -> Result<(), LoxError> {
-        if self.variables.contains_key(&name.lexeme) {
-            self.variables
-                .insert(name.lexeme.to_string(), value.clone());
+        if let Some(variable) = self.variable_mut(&name.symbol()) {
+            *variable = value.clone();
             Ok(())
         } else if let Some(ref mut enclosed) = self.parent_env {
             enclosed.borrow_mut().assign(name, value)
@@ -67,4 +119,34 @@ impl Environment {
             ))))
         }
     }
+
+    // A local the resolver found, no names involved
+    pub fn get_at(&self, slot: Slot) -> LoxValue {
+        match slot.depth {
+            0 => self.slots[slot.index].1.clone(),
+            depth => self.parent().borrow().get_at(Slot {
+                depth: depth - 1,
+                ..slot
+            }),
+        }
+    }
+
+    pub fn assign_at(&mut self, slot: Slot, value: &LoxValue) {
+        match slot.depth {
+            0 => self.slots[slot.index].1 = value.clone(),
+            depth => self.parent().borrow_mut().assign_at(
+                Slot {
+                    depth: depth - 1,
+                    ..slot
+                },
+                value,
+            ),
+        }
+    }
+
+    fn parent(&self) -> &Rc<RefCell<Environment>> {
+        self.parent_env
+            .as_ref()
+            .expect("resolved variables are never globals")
+    }
 }
@@ -1,27 +1,62 @@
-use crate::frontend::lox_callable::LoxCallable;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::{
     cell::RefCell,
-    io::{Cursor, Write},
+    io::{self, Cursor, Write},
 };
 
-use crate::frontend::lox_value::LoxValue;
+use crate::frontend::lox_value::{ErrorValue, LoxValue};
+use crate::frontend::token::Token;
 use crate::frontend::token_type::TokenType;
 use crate::tree_walker::environment::Environment;
-use crate::{InterpreterError, LoxError, RuntimeError};
+use crate::{Exception, InterpreterError, LoxError, RuntimeError};
 
+use super::arithmetic::{self, NumericPolicy};
+use super::builtins::{native, Argc, Argv};
+use super::coverage::Coverage;
+use super::debugger::{CallFrame, DebugSession, Debugger};
 use super::lox_function::LoxFunction;
-use super::parser::{Expr, Stmt};
+use super::optimizer::{optimize, OptLevel};
+use super::parser::{Ast, Expr, ExprId, ImportStmt, Parser, Program, Stmt, TryStmt};
+use super::profiler::{Profile, Profiler};
+use super::resolver::Resolver;
 
 // TODO: read about lifetimes and anonymous lifetimes!!
 
-#[derive(Debug, Clone)]
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     // We store env as a field directly in Interpreter so that the variables stay in memory as long as the interpreter is still running.
     environment: Rc<RefCell<Environment>>,
     //Write to an in memory buffer to test our interpreter:
     output_buffer: RefCell<Cursor<Vec<u8>>>,
+    // where `print` writes to, stdout unless a frontend like the debug adapter needs it
+    stdout: Box<dyn Write>,
+    // the script itself is the outermost frame, every function call pushes a frame on top
+    frames: Vec<CallFrame>,
+    calls: usize,
+    debug_session: Option<DebugSession>,
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>,
+    // the script that was started, imports in its own code are resolved relative to it
+    script_path: Option<PathBuf>,
+    // module whose code is running right now, `None` while the script's own code runs
+    module: Option<Rc<Path>>,
+    // `argc` and `argv`, the script and every module it imports can call them
+    script_natives: Vec<(&'static str, LoxValue)>,
+    // every module imported so far by its canonical path, `None` while it is still being loaded
+    modules: HashMap<PathBuf, Option<Rc<RefCell<Environment>>>>,
+    // files that are being loaded, the script first, to report how a cycle came about
+    import_stack: Vec<PathBuf>,
+    opt_level: OptLevel,
+    numeric_policy: NumericPolicy,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 // We rely on this helper method that sends the expression back into the interpreter's visitor
@@ -37,7 +72,141 @@ impl Interpreter {
             globals: Rc::clone(&globals),
             environment: Rc::clone(&globals), // Corrected line
             output_buffer: RefCell::new(Cursor::new(Vec::new())),
+            stdout: Box::new(io::stdout()),
+            frames: vec![CallFrame::new(0, "<script>", 0, Rc::clone(&globals))],
+            calls: 0,
+            debug_session: None,
+            profiler: None,
+            coverage: None,
+            script_path: None,
+            module: None,
+            script_natives: Vec::new(),
+            modules: HashMap::new(),
+            import_stack: Vec::new(),
+            opt_level: OptLevel::default(),
+            numeric_policy: NumericPolicy::default(),
+        }
+    }
+
+    // The file the script was read from, without it imports are relative to the working directory
+    pub fn set_script_path(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.modules.insert(path.clone(), None);
+        self.import_stack = vec![path.clone()];
+        self.script_path = Some(path);
+    }
+
+    // Expose the command line arguments of the script through `argc()` and `argv(n)`
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.script_natives = vec![
+            (
+                "argc",
+                LoxValue::Function(Rc::new(Argc { count: args.len() })),
+            ),
+            ("argv", LoxValue::Function(Rc::new(Argv { args }))),
+        ];
+    }
+
+    fn script_native(&self, name: &str) -> Option<LoxValue> {
+        self.script_natives
+            .iter()
+            .find(|(native, _)| *native == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    // From now on the code of `module` runs, `None` is the script. Returns the module that ran
+    // before so the caller can switch back to it.
+    pub(super) fn enter_module(&mut self, module: Option<Rc<Path>>) -> Option<Rc<Path>> {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.module = module.clone();
         }
+        std::mem::replace(&mut self.module, module)
+    }
+
+    // Programs, and the modules they import, are optimised before they run
+    pub fn set_opt_level(&mut self, level: OptLevel) {
+        self.opt_level = level;
+    }
+
+    pub fn set_numeric_policy(&mut self, policy: NumericPolicy) {
+        self.numeric_policy = policy;
+    }
+
+    pub fn numeric_policy(&self) -> NumericPolicy {
+        self.numeric_policy
+    }
+
+    pub fn set_stdout(&mut self, stdout: Box<dyn Write>) {
+        self.stdout = stdout;
+    }
+
+    // Every statement from now on checks with the debugger whether execution has to pause
+    pub fn attach_debugger(&mut self, debugger: Box<dyn Debugger>, stop_on_entry: bool) {
+        self.debug_session = Some(DebugSession::new(debugger, stop_on_entry));
+    }
+
+    // Record call counts, timings and line hits until the profile is taken
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(Profiler::new());
+    }
+
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profiler.take().map(Profiler::finish)
+    }
+
+    // Count executed statements and branches, `coverage` already knows every statement of the script
+    pub fn enable_coverage(&mut self, coverage: Coverage) {
+        self.coverage = Some(coverage);
+    }
+
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    // true when the debugger aborted the script
+    pub fn is_terminated(&self) -> bool {
+        self.debug_session
+            .as_ref()
+            .is_some_and(|session| session.is_terminated())
+    }
+
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.frames
+    }
+
+    // innermost scope of a frame, index 0 is the script
+    pub fn frame_environment(&self, frame: usize) -> Rc<RefCell<Environment>> {
+        if frame + 1 >= self.frames.len() {
+            Rc::clone(&self.environment)
+        } else {
+            Rc::clone(&self.frames[frame].environment)
+        }
+    }
+
+    // Evaluate an expression typed in by the user in the scope of the given frame
+    pub fn evaluate_in_frame(&mut self, source: &str, frame: usize) -> Result<LoxValue, LoxError> {
+        let (ast, expression) = Parser::from_source(source).parse_expression()?;
+
+        let frame_environment = self.frame_environment(frame);
+        let current = std::mem::replace(&mut self.environment, frame_environment);
+        let result = self.evaluate_expression(&Rc::new(ast), expression);
+        self.environment = current;
+        result
+    }
+
+    fn debug_hook(&mut self, line: usize) -> Result<(), LoxError> {
+        // take the session out so the debugger can use the interpreter while it is paused
+        if let Some(mut session) = self.debug_session.take() {
+            session.on_statement(self, line);
+            let terminated = session.is_terminated();
+            self.debug_session = Some(session);
+            if terminated {
+                return Err(LoxError::Runtime(RuntimeError::throw(
+                    "Debug session terminated.".to_string(),
+                )));
+            }
+        }
+        Ok(())
     }
 
     pub fn write_to_buffer(&self, text: &str) {
@@ -49,86 +218,321 @@ impl Interpreter {
         self.output_buffer.borrow().get_ref().clone()
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), LoxError> {
+    pub fn interpret(&mut self, program: Program) -> Result<(), LoxError> {
+        let Program {
+            statements,
+            mut ast,
+        } = optimize(program, self.opt_level);
+        Resolver::new(&mut ast).resolve(&statements);
+        let ast = Rc::new(ast);
         for statement in statements {
-            self.execute(&statement)?;
+            match self.execute(&ast, &statement) {
+                Ok(()) => (),
+                // a return outside of a function has no caller that could take the value
+                Err(LoxError::Return(_)) => {
+                    return Err(LoxError::Runtime(RuntimeError::throw(
+                        "Can't return from top-level code.".to_string(),
+                    )))
+                }
+                Err(e) => return Err(e),
+            }
         }
         Ok(())
     }
 
-    fn execute(&mut self, statement: &Stmt) -> Result<(), LoxError> {
+    // Back to the top level after a panic unwound the interpreter half way through a call
+    pub fn recover(&mut self) {
+        self.environment = Rc::clone(&self.globals);
+        self.frames.truncate(1);
+        if let Some(frame) = self.frames.first_mut() {
+            frame.environment = Rc::clone(&self.globals);
+        }
+    }
+
+    fn execute(&mut self, ast: &Rc<Ast>, statement: &Stmt) -> Result<(), LoxError> {
+        let line = statement.line();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.line = line;
+        }
+        // a block only opens a scope, its statements are tracked on their own lines and pausing
+        // on it would just be an extra step
+        if !matches!(statement, Stmt::Block(_)) {
+            // lines are tracked per file, a module has its own line numbers
+            let module = self.module.as_deref();
+            if let Some(profiler) = self.profiler.as_mut() {
+                profiler.on_line(module, line);
+            }
+            if let Some(coverage) = self.coverage.as_mut() {
+                coverage.file(module).hit_line(line);
+            }
+            if self.debug_session.is_some() {
+                self.debug_hook(line)?;
+            }
+        }
+
+        let result = self.execute_statement(ast, statement);
+
+        // the innermost statement knows best where a runtime error came from
+        match result {
+            Err(LoxError::Runtime(e)) => Err(LoxError::Runtime(e.at_line(line))),
+            result => result,
+        }
+    }
+
+    fn execute_statement(&mut self, ast: &Rc<Ast>, statement: &Stmt) -> Result<(), LoxError> {
         match statement {
             Stmt::Block(stmt) => {
-                let _block = self.execute_block(
+                self.execute_block(
+                    ast,
                     &stmt.statements,
                     // create a pointer to the current env
                     Environment::new_inner_environment(Rc::clone(&self.environment)),
-                );
-                Ok(())
+                )
             }
             Stmt::Expression(stmt) => {
-                let _expr = self.evaluate_expression(&stmt.expression)?;
+                let _expr = self.evaluate_expression(ast, stmt.expression)?;
                 Ok(())
             }
             Stmt::Function(fun) => {
-                let function = LoxFunction::new(fun.clone());
+                let function = LoxFunction::new(
+                    Rc::clone(fun),
+                    Rc::clone(ast),
+                    Rc::clone(&self.globals),
+                    self.module.clone(),
+                );
                 self.environment
                     .borrow_mut()
-                    .define(&fun.name.lexeme, LoxValue::Function(Rc::new(function)));
+                    .define(fun.name.symbol(), LoxValue::Function(Rc::new(function)));
 
                 Ok(())
             }
             Stmt::Print(stmt) => {
-                let value = self.evaluate_expression(&stmt.expression)?;
-                println!("{:?}\n", value);
+                let value = self.evaluate_expression(ast, stmt.expression)?;
+                let _ = writeln!(self.stdout, "{}", value.as_str());
                 // write to buffer so you get the output of the buffer for testing
                 self.write_to_buffer(&value.as_str());
                 Ok(())
             }
             Stmt::Var(stmt) => {
-                let value: LoxValue;
-                match &stmt.initializer {
-                    Some(expression) => value = self.evaluate_expression(&expression)?,
-                    None => value = LoxValue::Nil,
-                }
+                let value = match stmt.initializer {
+                    Some(expression) => self.evaluate_expression(ast, expression)?,
+                    None => LoxValue::Nil,
+                };
                 self.environment
                     .borrow_mut()
-                    .define(&stmt.name.lexeme, value);
+                    .define(stmt.name.symbol(), value);
                 Ok(())
             }
             Stmt::If(stmt) => {
-                let evaluate_if_condition = self.evaluate_expression(&stmt.condition)?;
+                let evaluate_if_condition = self.evaluate_expression(ast, stmt.condition)?;
+                let taken = self.is_truthy(&evaluate_if_condition);
+                if let Some(coverage) = self.coverage.as_mut() {
+                    coverage
+                        .file(self.module.as_deref())
+                        .hit_branch(stmt.line, taken);
+                }
 
-                if self.is_truthy(&evaluate_if_condition) {
-                    self.execute(&stmt.then_branch)
+                if taken {
+                    self.execute(ast, &stmt.then_branch)
                 } else if let Some(else_statement) = &stmt.else_branch {
-                    self.execute(else_statement)
+                    self.execute(ast, else_statement)
                 } else {
                     Ok(())
                 }
             }
             Stmt::While(stmt) => {
                 while {
-                    let condition_result = self.evaluate_expression(&stmt.condition)?;
+                    let condition_result = self.evaluate_expression(ast, stmt.condition)?;
+                    let taken = self.is_truthy(&condition_result);
+                    if let Some(coverage) = self.coverage.as_mut() {
+                        coverage
+                            .file(self.module.as_deref())
+                            .hit_branch(stmt.line, taken);
+                    }
 
-                    self.is_truthy(&condition_result)
+                    taken
                 } {
-                    let _ = self.execute(&stmt.body);
+                    self.execute(ast, &stmt.body)?;
                 }
                 Ok(())
             }
             Stmt::Return(stmt) => {
-                if let Some(value) = stmt.value.clone() {
-                    Err(LoxError::Return(self.evaluate_expression(&value)?))
+                if let Some(value) = stmt.value {
+                    Err(LoxError::Return(self.evaluate_expression(ast, value)?))
                 } else {
                     Err(LoxError::Return(LoxValue::Nil))
                 }
             }
+            Stmt::Import(stmt) => self.import(stmt),
+            Stmt::Throw(stmt) => {
+                let value = self.evaluate_expression(ast, stmt.value)?;
+                let stack = self
+                    .frames
+                    .iter()
+                    .rev()
+                    .map(|frame| (frame.function.clone(), frame.line))
+                    .collect();
+                Err(LoxError::Throw(Exception { value, stack }))
+            }
+            Stmt::Try(stmt) => self.execute_try(ast, stmt),
         }
 
         // return statement.accept(self);
     }
 
+    fn execute_try(&mut self, ast: &Rc<Ast>, stmt: &TryStmt) -> Result<(), LoxError> {
+        let mut result = self.execute_block(
+            ast,
+            &stmt.body,
+            Environment::new_inner_environment(Rc::clone(&self.environment)),
+        );
+
+        let caught = match (&result, &stmt.catch) {
+            (Err(error), Some(_)) => self.caught_value(error),
+            _ => None,
+        };
+        if let (Some(value), Some(catch)) = (caught, &stmt.catch) {
+            let mut scope = Environment::new_inner_environment(Rc::clone(&self.environment));
+            scope.define(catch.name.symbol(), value);
+            result = self.execute_block(ast, &catch.body, scope);
+        }
+
+        if let Some(finally) = &stmt.finally {
+            // an error or a return in the finally block wins over how the try was left
+            self.execute_block(
+                ast,
+                finally,
+                Environment::new_inner_environment(Rc::clone(&self.environment)),
+            )?;
+        }
+        result
+    }
+
+    // The value a `catch` binds for an error, returns and errors that end the script can't be
+    // caught
+    fn caught_value(&self, error: &LoxError) -> Option<LoxValue> {
+        let (message, line) = match error {
+            LoxError::Throw(exception) => return Some(exception.value.clone()),
+            LoxError::Runtime(_) if self.is_terminated() => return None,
+            LoxError::Runtime(e) => (e.message(), e.line().unwrap_or_default()),
+            LoxError::Interpreter(e) => (e.message(), e.line()),
+            LoxError::Return(_) | LoxError::ParserError(_) | LoxError::ScannerError(_) => {
+                return None
+            }
+        };
+        Some(LoxValue::Error(Rc::new(ErrorValue {
+            message: message.to_string(),
+            line,
+        })))
+    }
+
+    // Load the module (only the first time it is imported) and bind its exports in the current
+    // scope: every global not starting with `_`, or just the listed names
+    fn import(&mut self, stmt: &ImportStmt) -> Result<(), LoxError> {
+        let module = self.load_module(&stmt.path.lexeme)?;
+        let module = module.borrow();
+        match &stmt.names {
+            Some(names) => {
+                for name in names {
+                    let value = module.get_literal(name).map_err(|_| {
+                        LoxError::Runtime(RuntimeError::throw(format!(
+                            "Module \"{}\" doesn't export '{}'.",
+                            stmt.path.lexeme, name.lexeme
+                        )))
+                    })?;
+                    self.environment.borrow_mut().define(name.symbol(), value);
+                }
+            }
+            None => {
+                for (name, value) in module.bindings() {
+                    if !name.starts_with('_') {
+                        self.environment.borrow_mut().define(name.as_str(), value);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn load_module(&mut self, import_path: &str) -> Result<Rc<RefCell<Environment>>, LoxError> {
+        // a module imports relative to its own file
+        let base = self
+            .module
+            .as_deref()
+            .or(self.script_path.as_deref())
+            .and_then(Path::parent)
+            .map_or_else(PathBuf::new, Path::to_path_buf);
+        let cant_import = |e: io::Error| {
+            LoxError::Runtime(RuntimeError::throw(format!(
+                "Can't import \"{}\": {}",
+                import_path, e
+            )))
+        };
+        let path = base.join(import_path).canonicalize().map_err(cant_import)?;
+
+        match self.modules.get(&path) {
+            Some(Some(module)) => return Ok(Rc::clone(module)),
+            Some(None) => {
+                let cycle: Vec<String> = self
+                    .import_stack
+                    .iter()
+                    .skip_while(|loading| **loading != path)
+                    .chain(std::iter::once(&path))
+                    .map(|loading| loading.display().to_string())
+                    .collect();
+                return Err(LoxError::Runtime(RuntimeError::throw(format!(
+                    "Import cycle: {}",
+                    cycle.join(" -> ")
+                ))));
+            }
+            None => (),
+        }
+
+        let source = fs::read_to_string(&path).map_err(cant_import)?;
+        let Program {
+            statements,
+            mut ast,
+        } = optimize(Parser::from_source(&source).parse()?, self.opt_level);
+        Resolver::new(&mut ast).resolve(&statements);
+        let ast = Rc::new(ast);
+        if let Some(coverage) = self.coverage.as_mut() {
+            coverage.add_module(&path, &statements);
+        }
+
+        self.modules.insert(path.clone(), None);
+        self.import_stack.push(path.clone());
+        let module = Rc::new(RefCell::new(Environment::new()));
+        let globals = std::mem::replace(&mut self.globals, Rc::clone(&module));
+        let environment = std::mem::replace(&mut self.environment, Rc::clone(&module));
+        let caller = self.enter_module(Some(Rc::from(path.as_path())));
+
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(&ast, statement));
+
+        self.globals = globals;
+        self.environment = environment;
+        self.enter_module(caller);
+        self.import_stack.pop();
+
+        match result {
+            Ok(()) => {
+                self.modules.insert(path, Some(Rc::clone(&module)));
+                Ok(module)
+            }
+            Err(e) => {
+                // a failed module can be imported again once it is fixed
+                self.modules.remove(&path);
+                match e {
+                    LoxError::Return(_) => Err(LoxError::Runtime(RuntimeError::throw(
+                        "Can't return from top-level code.".to_string(),
+                    ))),
+                    e => Err(e),
+                }
+            }
+        }
+    }
+
     // we create a new env for blocks scope and pass it to this funciton
     // When i reset the code to the old env i was not keeping up to date with the previous
     // enviroment and this was becasue I was creating a new object via mem::replace so I was no
@@ -136,33 +540,41 @@ impl Interpreter {
     // code that messed everyitng up!
     // let previous = std::mem::replace(&mut *self.environment, *Box::new(env));
     // TODO: write in learned and look up details of std::mem::replace!
-    pub fn execute_block(&mut self, statements: &[Stmt], env: Environment) -> Result<(), LoxError> {
+    pub fn execute_block(
+        &mut self,
+        ast: &Rc<Ast>,
+        statements: &[Stmt],
+        env: Environment,
+    ) -> Result<(), LoxError> {
         // crate a pointer to the parrent env
         let parent_env = self.environment.clone();
         // new env that holds previous env as an enclosing field (BOX ENV)
 
-        {
-            self.environment = Rc::new(RefCell::new(env));
-            for stmt in statements {
-                self.execute(stmt)?;
-            }
-        }
+        self.environment = Rc::new(RefCell::new(env));
+        let result = statements
+            .iter()
+            .try_for_each(|stmt| self.execute(ast, stmt));
 
+        // restore the parent env even when a return or an error unwinds the block, otherwise the
+        // caller keeps evaluating in the scope of the callee
         self.environment = parent_env;
-        Ok(())
+        result
     }
 
-    fn evaluate_expression(&mut self, expression: &Expr) -> Result<LoxValue, LoxError> {
-        match expression {
+    fn evaluate_expression(&mut self, ast: &Rc<Ast>, id: ExprId) -> Result<LoxValue, LoxError> {
+        match &ast[id] {
             Expr::Assign(expr) => {
-                let value = self.evaluate_expression(&expr.value)?;
-                self.environment.borrow_mut().assign(&expr.name, &value)?;
+                let value = self.evaluate_expression(ast, expr.value)?;
+                match expr.slot {
+                    Some(slot) => self.environment.borrow_mut().assign_at(slot, &value),
+                    None => self.environment.borrow_mut().assign(&expr.name, &value)?,
+                }
                 Ok(value)
             }
             // OR and first is truthy return left
             // AND and first is false return left
             Expr::Logical(expr) => {
-                let left = self.evaluate_expression(&expr.left)?;
+                let left = self.evaluate_expression(ast, expr.left)?;
                 if expr.operator.token_type == TokenType::Or {
                     if self.is_truthy(&left) {
                         return Ok(left);
@@ -174,119 +586,69 @@ impl Interpreter {
                         return Ok(left);
                     }
                 }
-                let right = self.evaluate_expression(&expr.right)?;
+                let right = self.evaluate_expression(ast, expr.right)?;
                 Ok(right)
             }
-            Expr::Binary(expr) => {
-                let left = self.evaluate_expression(&expr.left)?;
-                let right = self.evaluate_expression(&expr.right)?;
-
-                match (&left, &right) {
-                    (LoxValue::Integer(left_value), LoxValue::Integer(right_value)) => {
-                        // println!("left: {:?} - right: {:?}", left_value, right_value);
-                        match expr.operator.token_type {
-                            TokenType::Minus => Ok(LoxValue::Integer(left_value - right_value)),
-                            TokenType::Slash => Ok(LoxValue::Integer(left_value / right_value)),
-                            TokenType::Star => Ok(LoxValue::Integer(left_value * right_value)),
-                            TokenType::Plus => Ok(LoxValue::Integer(left_value + right_value)),
-                            TokenType::Greater => Ok(LoxValue::Boolean(left_value > right_value)),
-                            TokenType::GreaterEqual => {
-                                Ok(LoxValue::Boolean(left_value >= right_value))
-                            }
-                            TokenType::Less => Ok(LoxValue::Boolean(left_value < right_value)),
-                            TokenType::LessEqual => {
-                                Ok(LoxValue::Boolean(left_value <= right_value))
-                            }
-                            TokenType::EqualEqual => {
-                                Ok(LoxValue::Boolean(left_value == right_value))
-                            }
-                            TokenType::BangEqual => {
-                                Ok(LoxValue::Boolean(left_value != right_value))
-                            }
-                            _ => Err(self.create_interpreter_error(
-                                expr.operator.line,
-                                &expr.operator.token_type,
-                                left,
-                                right,
-                            )),
-                        }
-                    }
-
-                    (LoxValue::String(left_value), LoxValue::String(right_value)) => {
-                        let mut left_value = left_value.clone();
-                        match expr.operator.token_type {
-                            TokenType::Plus => {
-                                left_value.push_str(&right_value);
-                                Ok(LoxValue::String(left_value.to_string()))
-                            }
-                            TokenType::EqualEqual => Ok(LoxValue::Boolean(left == right)),
-                            TokenType::BangEqual => Ok(LoxValue::Boolean(left != right)),
-                            _ => Err(self.create_interpreter_error(
-                                expr.operator.line,
-                                &expr.operator.token_type,
-                                left,
-                                right,
-                            )),
-                        }
-                    }
-
-                    (LoxValue::Nil, LoxValue::Nil) => match expr.operator.token_type {
-                        TokenType::EqualEqual => Ok(LoxValue::Boolean(true)),
-                        _ => Err(self.create_interpreter_error(
-                            expr.operator.line,
-                            &expr.operator.token_type,
-                            left,
-                            right,
-                        )),
-                    },
-                    (LoxValue::Nil, _) | (_, LoxValue::Nil) => match expr.operator.token_type {
-                        TokenType::EqualEqual => Ok(LoxValue::Boolean(false)),
-                        _ => Err(self.create_interpreter_error(
-                            expr.operator.line,
-                            &expr.operator.token_type,
-                            left,
-                            right,
-                        )),
-                    },
-                    _ => Err(self.create_interpreter_error(
-                        expr.operator.line,
-                        &expr.operator.token_type,
-                        left,
-                        right,
-                    )),
+            // only the branch picked by the condition is evaluated
+            Expr::Conditional(expr) => {
+                let condition = self.evaluate_expression(ast, expr.condition)?;
+                if self.is_truthy(&condition) {
+                    self.evaluate_expression(ast, expr.then_branch)
+                } else {
+                    self.evaluate_expression(ast, expr.else_branch)
                 }
             }
-            Expr::Grouping(expr) => self.evaluate_expression(&expr.expression),
+            Expr::Comma(expr) => {
+                self.evaluate_expression(ast, expr.left)?;
+                self.evaluate_expression(ast, expr.right)
+            }
+            Expr::Binary(expr) => {
+                let left = self.evaluate_expression(ast, expr.left)?;
+                let right = self.evaluate_expression(ast, expr.right)?;
+                binary(&expr.operator, left, right, self.numeric_policy)
+            }
+            Expr::Grouping(expr) => self.evaluate_expression(ast, expr.expression),
             Expr::Literal(expr) => Ok(expr.value.clone()),
             Expr::Unary(expr) => {
                 // first evauluate the operand subexpression before we evaluate the unary operator
                 // recursevly walk the AST
-                let right = self.evaluate_expression(&expr.right)?;
-
-                if expr.operator.token_type == TokenType::Minus {
-                    if let LoxValue::Integer(number) = right {
-                        return Ok(LoxValue::Integer(-number));
-                    } else {
-                        return Err(LoxError::Interpreter(InterpreterError::throw(
-                            expr.operator.line,
-                            format!("Operand: {:?} must be a number", right),
-                        )));
-                    }
-                } else if expr.operator.token_type == TokenType::Bang {
-                    let bool = self.is_truthy(&right);
-                    return Ok(LoxValue::Boolean(bool));
+                let right = self.evaluate_expression(ast, expr.right)?;
+                unary(&expr.operator, right)
+            }
+            Expr::Update(expr) => {
+                let slot = expr.slot;
+                let old = match slot {
+                    Some(slot) => self.environment.borrow().get_at(slot),
+                    None => self.environment.borrow().get_literal(&expr.name)?,
+                };
+                let new = binary(
+                    &expr.step(),
+                    old.clone(),
+                    LoxValue::Integer(1),
+                    self.numeric_policy,
+                )?;
+                match slot {
+                    Some(slot) => self.environment.borrow_mut().assign_at(slot, &new),
+                    None => self.environment.borrow_mut().assign(&expr.name, &new)?,
                 }
-                // unreachable
-                Ok(LoxValue::Nil)
+                Ok(if expr.prefix { new } else { old })
             }
             Expr::Variable(expr) => {
-                let env_value = self.environment.borrow().get_literal(&expr.name)?.clone();
+                let lookup = match expr.slot {
+                    Some(slot) => Ok(self.environment.borrow().get_at(slot)),
+                    None => self.environment.borrow().get_literal(&expr.name),
+                };
+                let env_value = lookup.or_else(|e| {
+                    native(&expr.name.lexeme)
+                        .or_else(|| self.script_native(&expr.name.lexeme))
+                        .ok_or(e)
+                })?;
                 // println!("stored_env_value: {}", env_value);
                 Ok(env_value)
             }
             Expr::Call(expr) => {
                 let callable = self
-                    .evaluate_expression(&expr.callee)?
+                    .evaluate_expression(ast, expr.callee)?
                     .get_callable()
                     .ok_or_else(|| {
                         LoxError::Runtime(RuntimeError::throw(format!(
@@ -298,9 +660,8 @@ impl Interpreter {
                 let mut arguments: Vec<LoxValue> = Vec::with_capacity(expr.arguments.len());
                 // TODO: go through evaluate expression. and see where we reuse the same n value!!
                 //
-                for expr in &expr.arguments {
-                    // println!("Expression to be evaluated: {} \n", expr);
-                    arguments.push(self.evaluate_expression(&expr)?);
+                for &argument in &expr.arguments {
+                    arguments.push(self.evaluate_expression(ast, argument)?);
                     // println!("arg pushed: {:?}", arguments);
                 }
                 // print!("arguments in Expr::Call: {:?} \n", arguments);
@@ -313,9 +674,29 @@ impl Interpreter {
                     )));
                 }
 
+                // the caller's scope is remembered so it can be inspected while the callee runs
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.environment = Rc::clone(&self.environment);
+                }
+                self.calls += 1;
+                self.frames.push(CallFrame::new(
+                    self.calls,
+                    callable.name(),
+                    expr.paren.line,
+                    Rc::clone(&self.globals),
+                ));
+
                 // NOTE: error can't be here because we execute the code block and all the rest
                 // with the wrong arguments!!
-                Ok(callable.call(self, arguments)?)
+                if let Some(profiler) = self.profiler.as_mut() {
+                    profiler.enter(callable.name());
+                }
+                let result = callable.call(self, arguments);
+                if let Some(profiler) = self.profiler.as_mut() {
+                    profiler.exit();
+                }
+                self.frames.pop();
+                result
             }
         }
 
@@ -323,25 +704,109 @@ impl Interpreter {
     }
 
     fn is_truthy(&mut self, right: &LoxValue) -> bool {
-        match right {
-            LoxValue::Nil | LoxValue::Boolean(false) => false,
-            _ => true,
+        right.is_truthy()
+    }
+}
+
+// The value of a binary operator, shared with the optimizer so folding a constant gives the same
+// value, or fails the same way, as running it
+pub(super) fn binary(
+    operator: &Token,
+    left: LoxValue,
+    right: LoxValue,
+    policy: NumericPolicy,
+) -> Result<LoxValue, LoxError> {
+    if arithmetic::is_bitwise(&operator.token_type) {
+        return arithmetic::bitwise(operator, &left, &right);
+    }
+    match (&left, &right) {
+        (LoxValue::Integer(_) | LoxValue::Float(_), LoxValue::Integer(_) | LoxValue::Float(_)) => {
+            arithmetic::binary(operator, &left, &right, policy)?.ok_or_else(|| {
+                operator_error(
+                    operator.line,
+                    &operator.token_type,
+                    left.clone(),
+                    right.clone(),
+                )
+            })
         }
+
+        (LoxValue::String(left_value), LoxValue::String(right_value)) => {
+            let mut left_value = left_value.clone();
+            match operator.token_type {
+                TokenType::Plus => {
+                    left_value.push_str(right_value);
+                    Ok(LoxValue::String(left_value.to_string()))
+                }
+                TokenType::EqualEqual => Ok(LoxValue::Boolean(left == right)),
+                TokenType::BangEqual => Ok(LoxValue::Boolean(left != right)),
+                _ => Err(operator_error(
+                    operator.line,
+                    &operator.token_type,
+                    left,
+                    right,
+                )),
+            }
+        }
+
+        (LoxValue::Nil, LoxValue::Nil) => match operator.token_type {
+            TokenType::EqualEqual => Ok(LoxValue::Boolean(true)),
+            _ => Err(operator_error(
+                operator.line,
+                &operator.token_type,
+                left,
+                right,
+            )),
+        },
+        (LoxValue::Nil, _) | (_, LoxValue::Nil) => match operator.token_type {
+            TokenType::EqualEqual => Ok(LoxValue::Boolean(false)),
+            _ => Err(operator_error(
+                operator.line,
+                &operator.token_type,
+                left,
+                right,
+            )),
+        },
+        _ => Err(operator_error(
+            operator.line,
+            &operator.token_type,
+            left,
+            right,
+        )),
     }
+}
 
-    fn create_interpreter_error(
-        &self,
-        location: usize,
-        token_type: &TokenType,
-        left: LoxValue,
-        right: LoxValue,
-    ) -> LoxError {
-        LoxError::Interpreter(InterpreterError::throw(
-            location,
-            format!(
-                "Execution of {:?} operator, is not supporterd for values: {}, {}",
-                token_type, left, right
-            ),
-        ))
+pub(super) fn unary(operator: &Token, right: LoxValue) -> Result<LoxValue, LoxError> {
+    if operator.token_type == TokenType::Minus {
+        if let Some(negated) = arithmetic::negate(operator, &right)? {
+            return Ok(negated);
+        } else {
+            return Err(LoxError::Interpreter(InterpreterError::throw(
+                operator.line,
+                format!("Operand: {:?} must be a number", right),
+            )));
+        }
+    } else if operator.token_type == TokenType::Bang {
+        let bool = right.is_truthy();
+        return Ok(LoxValue::Boolean(bool));
+    } else if operator.token_type == TokenType::Tilde {
+        return arithmetic::complement(operator, &right);
     }
+    // unreachable
+    Ok(LoxValue::Nil)
+}
+
+fn operator_error(
+    location: usize,
+    token_type: &TokenType,
+    left: LoxValue,
+    right: LoxValue,
+) -> LoxError {
+    LoxError::Interpreter(InterpreterError::throw(
+        location,
+        format!(
+            "Execution of {:?} operator, is not supporterd for values: {}, {}",
+            token_type, left, right
+        ),
+    ))
 }
@@ -3,18 +3,41 @@ use crate::{
     tree_walker::environment::Environment,
     LoxError,
 };
+use std::cell::RefCell;
+use std::path::Path;
 use std::rc::Rc;
 
-use super::{interpreter::Interpreter, parser::FunctionDecl};
+use super::{
+    interpreter::Interpreter,
+    parser::{Ast, FunctionDecl},
+};
 
 #[derive(Debug, Clone)]
 pub struct LoxFunction {
-    pub declaration: FunctionDecl,
+    // shared with the AST, declaring a function doesn't copy its body
+    pub declaration: Rc<FunctionDecl>,
+    // the expressions of the body live in the arena of the source the function was parsed from
+    pub ast: Rc<Ast>,
+    // globals of the module the function was declared in, an imported function keeps seeing its
+    // own module and not the globals of the script that called it
+    pub globals: Rc<RefCell<Environment>>,
+    // file of that module, `None` for the script
+    pub module: Option<Rc<Path>>,
 }
 
 impl LoxFunction {
-    pub fn new(declaration: FunctionDecl) -> Self {
-        Self { declaration }
+    pub fn new(
+        declaration: Rc<FunctionDecl>,
+        ast: Rc<Ast>,
+        globals: Rc<RefCell<Environment>>,
+        module: Option<Rc<Path>>,
+    ) -> Self {
+        Self {
+            declaration,
+            ast,
+            globals,
+            module,
+        }
     }
 }
 
@@ -51,13 +74,17 @@ impl LoxCallable for LoxFunction {
         //the environement.
         //the parent env gets restored after the function environment has been interpreted
         // NOTE: we new inner env has pointer to globals!!! ERROR found?
-        let mut env = Environment::new_inner_environment(Rc::clone(&interpreter.globals));
+        let mut env = Environment::new_inner_environment(Rc::clone(&self.globals));
         // println!(" \n environment variables: {:?} \n", env);
-        for (parameter, value) in self.declaration.parameters.iter().zip(args.iter()) {
-            env.define(&parameter.clone().lexeme, value.clone());
+        for (parameter, value) in self.declaration.parameters.iter().zip(args) {
+            env.define(parameter.symbol(), value);
         }
 
-        let result = interpreter.execute_block(&self.declaration.body, env);
+        let caller_globals = std::mem::replace(&mut interpreter.globals, Rc::clone(&self.globals));
+        let caller_module = interpreter.enter_module(self.module.clone());
+        let result = interpreter.execute_block(&self.ast, &self.declaration.body, env);
+        interpreter.enter_module(caller_module);
+        interpreter.globals = caller_globals;
 
         // TODO: I don't like that we wrap the return value in an error!!
         match result {
@@ -1,5 +1,12 @@
+pub mod arithmetic;
+pub mod ast_printer;
 pub mod builtins;
+pub mod coverage;
+pub mod debugger;
 pub mod environment;
 pub mod interpreter;
 pub mod lox_function;
+pub mod optimizer;
 pub mod parser;
+pub mod profiler;
+pub mod resolver;
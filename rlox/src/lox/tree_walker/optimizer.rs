@@ -0,0 +1,267 @@
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::frontend::lox_value::LoxValue;
+use crate::frontend::token_type::TokenType;
+
+use super::arithmetic::NumericPolicy;
+use super::interpreter::{binary, unary};
+use super::parser::{Ast, BlockStmt, Expr, ExprId, GroupingExpr, LiteralExpr, Program, Stmt};
+
+// How much the program gets rewritten before it runs, `--opt-level` on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptLevel {
+    // run the program as it was written
+    #[default]
+    None,
+    // fold operators whose operands are literals
+    Fold,
+    // also drop branches that can't run and expression statements that do nothing
+    Full,
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level {
+            "0" => Ok(OptLevel::None),
+            "1" => Ok(OptLevel::Fold),
+            "2" => Ok(OptLevel::Full),
+            _ => Err(format!("Unknown optimisation level '{}'", level)),
+        }
+    }
+}
+
+// Rewrites the program without changing what it does. An operator is only folded when running it
+// succeeds, `1 ~/ 0` stays in the tree and fails at runtime like it would have without the
+// optimizer, or never if the code doesn't run.
+pub fn optimize(program: Program, level: OptLevel) -> Program {
+    if level == OptLevel::None {
+        return program;
+    }
+    let Program {
+        statements,
+        mut ast,
+    } = program;
+    let statements = Optimizer {
+        level,
+        ast: &mut ast,
+    }
+    .statements(statements);
+    Program { statements, ast }
+}
+
+// Folded expressions are rewritten in place in the arena, an expression that is replaced by one
+// of its operands stays in the arena but nothing refers to it anymore
+struct Optimizer<'a> {
+    level: OptLevel,
+    ast: &'a mut Ast,
+}
+
+impl Optimizer<'_> {
+    fn statements(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        statements
+            .into_iter()
+            .filter_map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    // None when the statement can be left out
+    fn statement(&mut self, statement: Stmt) -> Option<Stmt> {
+        let full = self.level >= OptLevel::Full;
+        match statement {
+            Stmt::Expression(mut stmt) => {
+                stmt.expression = self.expression(stmt.expression);
+                if full && self.constant(stmt.expression).is_some() {
+                    return None;
+                }
+                Some(Stmt::Expression(stmt))
+            }
+            Stmt::Print(mut stmt) => {
+                stmt.expression = self.expression(stmt.expression);
+                Some(Stmt::Print(stmt))
+            }
+            Stmt::Function(fun) => {
+                let mut fun = Rc::unwrap_or_clone(fun);
+                fun.body = self.statements(fun.body);
+                Some(Stmt::Function(Rc::new(fun)))
+            }
+            Stmt::Var(mut stmt) => {
+                stmt.initializer = stmt.initializer.map(|value| self.expression(value));
+                Some(Stmt::Var(stmt))
+            }
+            Stmt::If(mut stmt) => {
+                stmt.condition = self.expression(stmt.condition);
+                let then_branch = self.statement(*stmt.then_branch);
+                let else_branch = stmt
+                    .else_branch
+                    .and_then(|else_branch| self.statement(*else_branch));
+                if let (true, Some(condition)) = (full, self.constant(stmt.condition)) {
+                    return if condition.is_truthy() {
+                        then_branch
+                    } else {
+                        else_branch
+                    };
+                }
+                stmt.then_branch = Box::new(then_branch.unwrap_or_else(|| empty_block(stmt.line)));
+                stmt.else_branch = else_branch.map(Box::new);
+                Some(Stmt::If(stmt))
+            }
+            Stmt::Return(mut stmt) => {
+                stmt.value = stmt.value.map(|value| self.expression(value));
+                Some(Stmt::Return(stmt))
+            }
+            Stmt::Block(mut stmt) => {
+                stmt.statements = self.statements(stmt.statements);
+                Some(Stmt::Block(stmt))
+            }
+            Stmt::While(mut stmt) => {
+                stmt.condition = self.expression(stmt.condition);
+                if full
+                    && self
+                        .constant(stmt.condition)
+                        .is_some_and(|value| !value.is_truthy())
+                {
+                    return None;
+                }
+                stmt.body = Box::new(
+                    self.statement(*stmt.body)
+                        .unwrap_or_else(|| empty_block(stmt.line)),
+                );
+                Some(Stmt::While(stmt))
+            }
+            Stmt::Import(stmt) => Some(Stmt::Import(stmt)),
+            Stmt::Throw(mut stmt) => {
+                stmt.value = self.expression(stmt.value);
+                Some(Stmt::Throw(stmt))
+            }
+            Stmt::Try(mut stmt) => {
+                stmt.body = self.statements(stmt.body);
+                if let Some(catch) = stmt.catch.as_mut() {
+                    catch.body = self.statements(std::mem::take(&mut catch.body));
+                }
+                stmt.finally = stmt.finally.map(|finally| self.statements(finally));
+                Some(Stmt::Try(stmt))
+            }
+        }
+    }
+
+    // The id the expression has after the rewrite, an operand when it took the place of the
+    // expression
+    fn expression(&mut self, id: ExprId) -> ExprId {
+        // the expression is taken out of the arena while its operands are rewritten, a grouping
+        // is a placeholder that doesn't allocate
+        let placeholder = Expr::Grouping(GroupingExpr { expression: id });
+        match std::mem::replace(&mut self.ast[id], placeholder) {
+            Expr::Binary(mut expr) => {
+                expr.left = self.expression(expr.left);
+                expr.right = self.expression(expr.right);
+                if let (Some(a), Some(b)) = (self.constant(expr.left), self.constant(expr.right)) {
+                    // folding under the strict policy leaves infinity and NaN to runtime, where
+                    // the policy of the interpreter decides
+                    if let Ok(value) =
+                        binary(&expr.operator, a.clone(), b.clone(), NumericPolicy::Error)
+                    {
+                        return self.fold(id, value, expr.left);
+                    }
+                }
+                self.put(id, Expr::Binary(expr))
+            }
+            Expr::Unary(mut expr) => {
+                expr.right = self.expression(expr.right);
+                if let Some(value) = self.constant(expr.right) {
+                    if let Ok(value) = unary(&expr.operator, value.clone()) {
+                        return self.fold(id, value, expr.right);
+                    }
+                }
+                self.put(id, Expr::Unary(expr))
+            }
+            // `and` and `or` return one of their operands, a constant left one decides which
+            Expr::Logical(mut expr) => {
+                expr.left = self.expression(expr.left);
+                expr.right = self.expression(expr.right);
+                if let Some(truthy) = self.constant(expr.left).map(LoxValue::is_truthy) {
+                    let is_or = expr.operator.token_type == TokenType::Or;
+                    return if truthy == is_or {
+                        expr.left
+                    } else {
+                        expr.right
+                    };
+                }
+                self.put(id, Expr::Logical(expr))
+            }
+            Expr::Conditional(mut expr) => {
+                expr.condition = self.expression(expr.condition);
+                expr.then_branch = self.expression(expr.then_branch);
+                expr.else_branch = self.expression(expr.else_branch);
+                if let Some(truthy) = self.constant(expr.condition).map(LoxValue::is_truthy) {
+                    return if truthy {
+                        expr.then_branch
+                    } else {
+                        expr.else_branch
+                    };
+                }
+                self.put(id, Expr::Conditional(expr))
+            }
+            // a constant on the left does nothing, the value is the right operand
+            Expr::Comma(mut expr) => {
+                expr.left = self.expression(expr.left);
+                expr.right = self.expression(expr.right);
+                if self.constant(expr.left).is_some() {
+                    return expr.right;
+                }
+                self.put(id, Expr::Comma(expr))
+            }
+            Expr::Grouping(mut expr) => {
+                expr.expression = self.expression(expr.expression);
+                if self.constant(expr.expression).is_some() {
+                    return expr.expression;
+                }
+                self.put(id, Expr::Grouping(expr))
+            }
+            Expr::Assign(mut expr) => {
+                expr.value = self.expression(expr.value);
+                self.put(id, Expr::Assign(expr))
+            }
+            Expr::Call(mut expr) => {
+                expr.callee = self.expression(expr.callee);
+                for argument in expr.arguments.iter_mut() {
+                    *argument = self.expression(*argument);
+                }
+                self.put(id, Expr::Call(expr))
+            }
+            expression @ (Expr::Literal(_) | Expr::Update(_) | Expr::Variable(_)) => {
+                self.put(id, expression)
+            }
+        }
+    }
+
+    fn put(&mut self, id: ExprId, expression: Expr) -> ExprId {
+        self.ast[id] = expression;
+        id
+    }
+
+    // The folded value keeps the token of the first operand, that is where it was written
+    fn fold(&mut self, id: ExprId, value: LoxValue, first: ExprId) -> ExprId {
+        let Expr::Literal(first) = &self.ast[first] else {
+            unreachable!("only literals are folded");
+        };
+        let token = first.token.clone();
+        self.put(id, Expr::Literal(LiteralExpr { value, token }))
+    }
+
+    fn constant(&self, id: ExprId) -> Option<&LoxValue> {
+        match &self.ast[id] {
+            Expr::Literal(literal) => Some(&literal.value),
+            _ => None,
+        }
+    }
+}
+
+fn empty_block(line: usize) -> Stmt {
+    Stmt::Block(BlockStmt {
+        statements: Vec::new(),
+        line,
+    })
+}
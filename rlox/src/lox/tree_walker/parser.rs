@@ -1,71 +1,134 @@
-use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::rc::Rc;
 
 use crate::frontend::lox_value::LoxValue;
+use crate::frontend::scanner::Scanner;
 
-use crate::frontend::token::Token;
+use crate::frontend::token::{Token, TokenRef};
 use crate::frontend::token_type::TokenType::{self, *};
 use crate::{Loc, LoxError, ParserError};
 
+use super::builtins::Str;
+use super::resolver::Slot;
+
 const PARAM_LIMIT: usize = 255;
 
 // NOTE: STATEMENTS
 #[derive(Debug, Clone)]
 pub enum Stmt {
     Expression(ExpressionStmt),
-    Function(FunctionDecl),
+    Function(Rc<FunctionDecl>),
     Var(VarStmt),
     If(IfStmt),
     Print(PrintStmt),
     Return(ReturnStmt),
     Block(BlockStmt),
     While(WhileStmt),
+    Import(ImportStmt),
+    Throw(ThrowStmt),
+    Try(TryStmt),
 }
 
 #[derive(Debug, Clone)]
 pub struct BlockStmt {
     pub statements: Vec<Stmt>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportStmt {
+    pub keyword: Token,
+    // string token with the path of the module, relative to the importing file
+    pub path: Token,
+    // `import { a, b } from "..."` only binds these, a plain `import "..."` binds every export
+    pub names: Option<Vec<Token>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThrowStmt {
+    pub keyword: Token,
+    pub value: ExprId,
+}
+
+#[derive(Debug, Clone)]
+pub struct TryStmt {
+    pub keyword: Token,
+    pub body: Vec<Stmt>,
+    pub catch: Option<CatchClause>,
+    // runs after the body and the catch block, however they were left
+    pub finally: Option<Vec<Stmt>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    // bound to the thrown value, or to an error value when the interpreter failed
+    pub name: Token,
+    pub body: Vec<Stmt>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ReturnStmt {
     pub keyword: Token,
-    pub value: Option<Expr>,
+    pub value: Option<ExprId>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionDecl {
     pub name: Token,
     pub parameters: Vec<Token>,
-    pub body: Box<Vec<Stmt>>,
+    pub body: Vec<Stmt>,
 }
 
 #[derive(Debug, Clone)]
 pub struct WhileStmt {
-    pub condition: Expr,
+    pub condition: ExprId,
     pub body: Box<Stmt>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct IfStmt {
-    pub condition: Expr,
+    pub condition: ExprId,
     pub then_branch: Box<Stmt>,
     pub else_branch: Option<Box<Stmt>>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct ExpressionStmt {
-    pub expression: Expr,
+    pub expression: ExprId,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct PrintStmt {
-    pub expression: Expr,
+    pub expression: ExprId,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct VarStmt {
     pub name: Token,
-    pub initializer: Option<Expr>,
+    pub initializer: Option<ExprId>,
+}
+
+impl Stmt {
+    // line the statement starts on, used to report errors and to stop on breakpoints
+    pub fn line(&self) -> usize {
+        match self {
+            Stmt::Expression(stmt) => stmt.line,
+            Stmt::Function(fun) => fun.name.line,
+            Stmt::Var(stmt) => stmt.name.line,
+            Stmt::If(stmt) => stmt.line,
+            Stmt::Print(stmt) => stmt.line,
+            Stmt::Return(stmt) => stmt.keyword.line,
+            Stmt::Import(stmt) => stmt.keyword.line,
+            Stmt::Throw(stmt) => stmt.keyword.line,
+            Stmt::Try(stmt) => stmt.keyword.line,
+            Stmt::Block(stmt) => stmt.line,
+            Stmt::While(stmt) => stmt.line,
+        }
+    }
 }
 
 // NOTE: EXPRESSIONS
@@ -75,161 +138,268 @@ pub enum Expr {
     Assign(AssignExpr),
     Binary(BinaryExpr),
     Call(FunctionCallExpr),
+    Comma(CommaExpr),
+    Conditional(ConditionalExpr),
     Grouping(GroupingExpr),
     Literal(LiteralExpr),
     Logical(LogicalExpr),
     Unary(UnaryExpr),
+    Update(UpdateExpr),
     Variable(VariableExpr),
 }
 
-impl fmt::Display for Expr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Expr::Assign(assign_expr) => write!(f, "ASSIGN_EXPR - {}", assign_expr),
-            Expr::Binary(binary_expr) => write!(f, "BINARY_EXPR - {}", binary_expr),
-            Expr::Call(function_call_expr) => write!(f, "CALL_EXPR - {}", function_call_expr),
-            Expr::Grouping(grouping_expr) => write!(f, "GROUPING_EXPR - {}", grouping_expr),
-            Expr::Literal(literal_expr) => write!(f, "LITERAL_EXPR - {}", literal_expr),
-            Expr::Logical(logical_expr) => write!(f, "LOGICAL_EXPR - {}", logical_expr),
-            Expr::Unary(unary_expr) => write!(f, "UNARY_EXPR - {}", unary_expr),
-            Expr::Variable(variable_expr) => write!(f, "VARIABLE_EXPR - {}", variable_expr),
-        }
+// Where an expression lives in the `Ast` of the source it was parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExprId(u32);
+
+// Every expression of a source in one arena. Expressions refer to their operands, and statements
+// to their expressions, by id, so a program is a few vectors instead of a box per node.
+#[derive(Debug, Clone, Default)]
+pub struct Ast {
+    exprs: Vec<Expr>,
+}
+
+impl Ast {
+    pub fn add(&mut self, expr: Expr) -> ExprId {
+        self.exprs.push(expr);
+        ExprId(self.exprs.len() as u32 - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct AssignExpr {
-    pub name: Token,
-    pub value: Box<Expr>,
+impl Index<ExprId> for Ast {
+    type Output = Expr;
+
+    fn index(&self, id: ExprId) -> &Expr {
+        &self.exprs[id.0 as usize]
+    }
 }
 
-impl fmt::Display for AssignExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} = {}", self.name, self.value)
+impl IndexMut<ExprId> for Ast {
+    fn index_mut(&mut self, id: ExprId) -> &mut Expr {
+        &mut self.exprs[id.0 as usize]
     }
 }
 
+// What the parser makes of a source, the statements and the arena of their expressions
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub statements: Vec<Stmt>,
+    pub ast: Ast,
+}
+
 #[derive(Debug, Clone)]
-pub struct BinaryExpr {
-    pub left: Box<Expr>,
-    pub operator: Token,
-    pub right: Box<Expr>,
+pub struct AssignExpr {
+    pub name: Token,
+    pub value: ExprId,
+    // filled in by the resolver for locals, globals are looked up by name
+    pub slot: Option<Slot>,
 }
 
-impl fmt::Display for BinaryExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({} {} {})", self.left, self.operator, self.right)
-    }
+#[derive(Debug, Clone)]
+pub struct BinaryExpr {
+    pub left: ExprId,
+    pub operator: Token,
+    pub right: ExprId,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionCallExpr {
-    pub callee: Box<Expr>,
+    pub callee: ExprId,
     pub paren: Token,
-    pub arguments: Vec<Expr>,
-}
-
-impl fmt::Display for FunctionCallExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}({})",
-            self.callee,
-            self.arguments
-                .iter()
-                .map(|arg| arg.to_string())
-                .collect::<Vec<_>>()
-                .join(", ")
-        )
-    }
+    pub arguments: Vec<ExprId>,
 }
 
+// `left, right` evaluates both operands and keeps the value of the right one
 #[derive(Debug, Clone)]
-pub struct LogicalExpr {
-    pub left: Box<Expr>,
+pub struct CommaExpr {
+    pub left: ExprId,
     pub operator: Token,
-    pub right: Box<Expr>,
+    pub right: ExprId,
 }
 
-impl fmt::Display for LogicalExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({} {} {})", self.left, self.operator, self.right)
-    }
+#[derive(Debug, Clone)]
+pub struct ConditionalExpr {
+    pub condition: ExprId,
+    // the `?`
+    pub operator: Token,
+    pub then_branch: ExprId,
+    pub else_branch: ExprId,
 }
 
 #[derive(Debug, Clone)]
-pub struct GroupingExpr {
-    pub expression: Box<Expr>,
-}
-impl fmt::Display for GroupingExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({})", self.expression)
-    }
+pub struct LogicalExpr {
+    pub left: ExprId,
+    pub operator: Token,
+    pub right: ExprId,
 }
 
+#[derive(Debug, Clone)]
+pub struct GroupingExpr {
+    pub expression: ExprId,
+}
 #[derive(Debug, Clone)]
 pub struct LiteralExpr {
     pub value: LoxValue,
+    // where the literal was written, the `true` of a `for (;;)` points at the `for`
+    pub token: Token,
 }
-impl fmt::Display for LiteralExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.value)
-    }
+#[derive(Debug, Clone)]
+pub struct UnaryExpr {
+    pub operator: Token,
+    pub right: ExprId,
 }
 
+// `++x` and `x--` add or subtract 1 and store the result in the variable, the prefix form
+// evaluates to the new value and the postfix form to the value the variable had before
 #[derive(Debug, Clone)]
-pub struct UnaryExpr {
+pub struct UpdateExpr {
+    pub name: Token,
+    // the `++` or `--`
     pub operator: Token,
-    pub right: Box<Expr>,
+    pub prefix: bool,
+    pub slot: Option<Slot>,
 }
 
-impl fmt::Display for UnaryExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}{})", self.operator, self.right)
+impl UpdateExpr {
+    // The binary operator applied to the variable and 1
+    pub fn step(&self) -> Token {
+        let token_type = match self.operator.token_type {
+            PlusPlus => Plus,
+            _ => Minus,
+        };
+        Token::new(
+            token_type,
+            self.operator.lexeme[..1].to_string(),
+            None,
+            self.operator.line,
+            self.operator.column,
+        )
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct VariableExpr {
     pub name: Token,
+    pub slot: Option<Slot>,
 }
 
-impl fmt::Display for VariableExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.name)
-    }
-}
-
+// Pulls tokens from the scanner one at a time, only the current and the previous token are kept
 #[allow(dead_code, unused_variables)]
-#[derive(Debug)]
 pub struct Parser<'a> {
-    tokens: &'a Vec<Token>,
-    current: usize,
+    tokens: Box<dyn Iterator<Item = Result<TokenRef<'a>, LoxError>> + 'a>,
+    previous: Option<TokenRef<'a>>,
+    current: TokenRef<'a>,
+    // the scanner stopped at an error, `current` is an `Eof` standing in for the rest of the source
+    scan_error: Option<LoxError>,
+    // where the expressions parsed so far go
+    ast: Ast,
 }
 
 #[allow(dead_code, unused_variables)]
 impl<'a> Parser<'a> {
-    pub fn build_parser(tokens: &Vec<Token>) -> Parser {
-        Parser { tokens, current: 0 }
+    pub fn build_parser(tokens: &'a [Token]) -> Parser<'a> {
+        Parser::from_tokens(tokens.iter().map(|token| Ok(token.borrow())))
+    }
+
+    // Scan the source while parsing it
+    pub fn from_source(source: &'a str) -> Parser<'a> {
+        Parser::from_tokens(Scanner::build_scanner(source))
+    }
+
+    fn from_tokens(
+        tokens: impl Iterator<Item = Result<TokenRef<'a>, LoxError>> + 'a,
+    ) -> Parser<'a> {
+        let mut parser = Parser {
+            tokens: Box::new(tokens),
+            previous: None,
+            current: end_of_file(1),
+            scan_error: None,
+            ast: Ast::default(),
+        };
+        parser.current = parser.next_token();
+        parser
     }
 
     // program        → statement* EOF ;
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, LoxError> {
+    pub fn parse(&mut self) -> Result<Program, LoxError> {
         let mut statements: Vec<Stmt> = Vec::new();
 
-        // TODO: with if let we can call synchronize() in case of an error
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(error) => return self.finish(Err(error)),
+            }
+        }
+
+        let program = self.program(statements);
+        self.finish(Ok(program))
+    }
+
+    // Keeps parsing after a syntax error so tooling gets every error in the file together with
+    // the statements that did parse
+    pub fn parse_recovering(&mut self) -> (Program, Vec<LoxError>) {
+        let mut statements: Vec<Stmt> = Vec::new();
+        let mut errors: Vec<LoxError> = Vec::new();
+
         while !self.is_at_end() {
             match self.declaration() {
                 Ok(stmt) => statements.push(stmt),
                 Err(error) => {
-                    println!("Error: {:?}", error);
-                    let _ = self.synchronize();
-                    return Err(error);
+                    // after a scanner error the parser only complains about the missing rest
+                    if self.scan_error.is_none() {
+                        errors.push(error);
+                    }
+                    self.synchronize();
                 }
             }
         }
+        errors.extend(self.scan_error.take());
 
-        Ok(statements)
+        (self.program(statements), errors)
+    }
+
+    // A single expression that has to use up every token, e.g. an expression typed into the
+    // debugger prompt
+    pub fn parse_expression(&mut self) -> Result<(Ast, ExprId), LoxError> {
+        let expr = self.expression().and_then(|expr| {
+            if self.is_at_end() {
+                return Ok((std::mem::take(&mut self.ast), expr));
+            }
+            let token = self.peek().unwrap();
+            Err(LoxError::ParserError(ParserError::new(
+                token.line,
+                Loc::Lexeme(token.lexeme.to_owned()),
+                "Expect end of expression.",
+            )))
+        });
+        self.finish(expr)
+    }
+
+    fn program(&mut self, statements: Vec<Stmt>) -> Program {
+        Program {
+            statements,
+            ast: std::mem::take(&mut self.ast),
+        }
+    }
+
+    // A scanner error anywhere in the source is reported before any syntax error, the same as
+    // when the whole source got scanned up front
+    fn finish<T>(&mut self, result: Result<T, LoxError>) -> Result<T, LoxError> {
+        while !self.is_at_end() {
+            self.advance();
+        }
+        match self.scan_error.take() {
+            Some(error) => Err(error),
+            None => result,
+        }
     }
 
     fn declaration(&mut self) -> Result<Stmt, LoxError> {
@@ -237,6 +407,8 @@ impl<'a> Parser<'a> {
             Ok(self.parse_function_statement("function")?)
         } else if self.match_token_types(&[Var]) {
             Ok(self.var_declaration()?)
+        } else if self.match_token_types(&[Import]) {
+            self.import_declaration()
         } else {
             Ok(self.statement()?)
         }
@@ -246,31 +418,40 @@ impl<'a> Parser<'a> {
     // statement      → exprStmt | printStmt ;
     fn statement(&mut self) -> Result<Stmt, LoxError> {
         if self.match_token_types(&[For]) {
-            return Ok(self.parse_for_statement()?);
+            return self.parse_for_statement();
         }
         if self.match_token_types(&[If]) {
-            return Ok(self.parse_if_statement()?);
+            return self.parse_if_statement();
         }
 
         if self.match_token_types(&[Print]) {
-            return Ok(self.parse_print_statement()?);
+            return self.parse_print_statement();
         }
         if self.match_token_types(&[Return]) {
-            return Ok(self.return_statment()?);
+            return self.return_statment();
+        }
+        if self.match_token_types(&[Throw]) {
+            return self.throw_statement();
+        }
+        if self.match_token_types(&[Try]) {
+            return self.try_statement();
         }
         if self.match_token_types(&[While]) {
             return Ok(self.parse_while_statement())?;
         }
         if self.match_token_types(&[LeftBrace]) {
+            let line = self.previous().unwrap().line;
             return Ok(Stmt::Block(BlockStmt {
                 statements: self.block()?,
+                line,
             }));
         }
 
-        Ok(self.expression_statement()?)
+        self.expression_statement()
     }
 
     fn parse_if_statement(&mut self) -> Result<Stmt, LoxError> {
+        let line = self.previous().unwrap().line;
         self.consume(LeftParen, "Expect '(', after 'if'")?;
         let condition = self.expression()?;
         self.consume(RightParen, "Expect '(', after if condition '")?;
@@ -285,6 +466,7 @@ impl<'a> Parser<'a> {
             condition,
             then_branch,
             else_branch,
+            line,
         }))
     }
     fn var_declaration(&mut self) -> Result<Stmt, LoxError> {
@@ -296,11 +478,11 @@ impl<'a> Parser<'a> {
             // Rust doesn't allow moving out of borrowed content within the same scope
             // Creating a new scope allows the borrow of `name_token` to end before
             // attempting to move or clone it
-            name_token.clone()
+            name_token.to_token()
             // Inner scope ends here
         };
 
-        let mut initializer: Option<Expr> = None;
+        let mut initializer: Option<ExprId> = None;
         if self.match_token_types(&[Equal]) {
             initializer = Some(self.expression()?);
         }
@@ -309,7 +491,48 @@ impl<'a> Parser<'a> {
         Ok(Stmt::Var(VarStmt { name, initializer }))
     }
 
+    // import → "import" ( "{" IDENTIFIER ( "," IDENTIFIER )* "}" "from" )? STRING ";" ;
+    fn import_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().unwrap().to_token();
+
+        let mut names = None;
+        if self.match_token_types(&[LeftBrace]) {
+            let mut imported = vec![self
+                .consume(Identifier, "Expect name to import.")?
+                .to_token()];
+            while self.match_token_types(&[Comma]) {
+                imported.push(
+                    self.consume(Identifier, "Expect name to import.")?
+                        .to_token(),
+                );
+            }
+            self.consume(RightBrace, "Expect '}' after imported names.")?;
+            // `from` is only special here, it stays usable as a variable name
+            if !(self.check(&Identifier) && self.peek().unwrap().lexeme == "from") {
+                let token = self.peek().unwrap();
+                return Err(LoxError::ParserError(ParserError::new(
+                    token.line,
+                    Loc::Lexeme(token.lexeme.to_owned()),
+                    "Expect 'from' after imported names.",
+                )));
+            }
+            self.advance();
+            names = Some(imported);
+        }
+
+        let path = self
+            .consume(String, "Expect module path string.")?
+            .to_token();
+        self.consume(Semicolon, "Expect ';' after import.")?;
+        Ok(Stmt::Import(ImportStmt {
+            keyword,
+            path,
+            names,
+        }))
+    }
+
     fn parse_while_statement(&mut self) -> Result<Stmt, LoxError> {
+        let line = self.previous().unwrap().line;
         self.consume(LeftParen, "Expect '(' after 'while'.)")?;
         let condition = self.expression()?;
         self.consume(RightParen, "Expect ')' after condition. ")?;
@@ -317,25 +540,31 @@ impl<'a> Parser<'a> {
         Ok(Stmt::While(WhileStmt {
             condition,
             body: Box::new(body),
+            line,
         }))
     }
 
     // printStmt      → "print" expression ";" ;
     fn parse_print_statement(&mut self) -> Result<Stmt, LoxError> {
+        let line = self.previous().unwrap().line;
         let value = self.expression()?;
         self.consume(Semicolon, "Expect ';' after value.")?;
-        Ok(Stmt::Print(PrintStmt { expression: value }))
+        Ok(Stmt::Print(PrintStmt {
+            expression: value,
+            line,
+        }))
     }
 
     // returnStmt     → "return" expression? ";" ;
     fn return_statment(&mut self) -> Result<Stmt, LoxError> {
+        // the `return` keyword was just consumed by match_token_types
+        let token = self.previous().unwrap().to_token();
         let expr = if !self.check(&Semicolon) {
             Some(self.expression()?)
         } else {
             None
         };
 
-        let token = self.previous().unwrap().clone();
         self.consume(Semicolon, "Expect ';' after return value")?;
         Ok(Stmt::Return(ReturnStmt {
             keyword: token,
@@ -343,15 +572,71 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    // throwStmt      → "throw" expression ";" ;
+    fn throw_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().unwrap().to_token();
+        let value = self.expression()?;
+        self.consume(Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw(ThrowStmt { keyword, value }))
+    }
+
+    // tryStmt        → "try" block ( "catch" "(" IDENTIFIER ")" block )? ( "finally" block )? ;
+    // at least one of catch and finally has to follow the try block
+    fn try_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().unwrap().to_token();
+        self.consume(LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = if self.match_token_types(&[Catch]) {
+            self.consume(LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self
+                .consume(Identifier, "Expect name of the caught value.")?
+                .to_token();
+            self.consume(RightParen, "Expect ')' after caught value.")?;
+            self.consume(LeftBrace, "Expect '{' after catch.")?;
+            Some(CatchClause {
+                name,
+                body: self.block()?,
+            })
+        } else {
+            None
+        };
+
+        let finally = if self.match_token_types(&[Finally]) {
+            self.consume(LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            let token = self.peek().unwrap();
+            return Err(LoxError::ParserError(ParserError::new(
+                token.line,
+                Loc::Lexeme(token.lexeme.to_owned()),
+                "Expect 'catch' or 'finally' after try block.",
+            )));
+        }
+        Ok(Stmt::Try(TryStmt {
+            keyword,
+            body,
+            catch,
+            finally,
+        }))
+    }
+
     // exprStmt       → expression ";" ;
     fn expression_statement(&mut self) -> Result<Stmt, LoxError> {
+        let line = self.peek().unwrap().line;
         let expression = self.expression()?;
         self.consume(Semicolon, "Expect ';' expression.")?;
-        Ok(Stmt::Expression(ExpressionStmt { expression }))
+        Ok(Stmt::Expression(ExpressionStmt { expression, line }))
     }
 
     fn parse_function_statement(&mut self, kind: &str) -> Result<Stmt, LoxError> {
-        let name = self.consume(Identifier, &format!("Expect {} name.", kind).as_str())?;
+        let name = self
+            .consume(Identifier, format!("Expect {} name.", kind).as_str())?
+            .to_token();
         let _ignore = self.consume(
             LeftParen,
             format!("Expect ( after {} name.", &kind).as_str(),
@@ -359,11 +644,11 @@ impl<'a> Parser<'a> {
 
         let (parameters, body) = self.parse_fun_parameters_and_body()?;
 
-        Ok(Stmt::Function(FunctionDecl {
+        Ok(Stmt::Function(Rc::new(FunctionDecl {
             name,
             parameters,
-            body: Box::new(body),
-        }))
+            body,
+        })))
     }
 
     fn parse_fun_parameters_and_body(&mut self) -> Result<(Vec<Token>, Vec<Stmt>), LoxError> {
@@ -380,7 +665,10 @@ impl<'a> Parser<'a> {
                     )));
                 }
 
-                parameters.push(self.consume(Identifier, "Expect parameter name.")?);
+                parameters.push(
+                    self.consume(Identifier, "Expect parameter name.")?
+                        .to_token(),
+                );
 
                 if !self.match_token_types(&[Comma]) {
                     break;
@@ -404,144 +692,295 @@ impl<'a> Parser<'a> {
         Ok(statements)
     }
 
-    // expression     → assingment ;
-    fn expression(&mut self) -> Result<Expr, LoxError> {
-        Ok(self.assignment()?)
+    // expression     → comma ;
+    fn expression(&mut self) -> Result<ExprId, LoxError> {
+        self.comma()
     }
 
-    // assignment     → IDENTIFIER "=" assignment | equality ;
+    // comma          → assignment ( "," assignment )* ;
+    // arguments are parsed with `assignment`, inside an argument list the comma separates them
+    fn comma(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.assignment()?;
+        while self.match_token_types(&[Comma]) {
+            let operator = self.previous().unwrap().to_token();
+            let right = self.assignment()?;
+            expr = self.ast.add(Expr::Comma(CommaExpr {
+                left: expr,
+                operator,
+                right,
+            }));
+        }
+
+        Ok(expr)
+    }
+
+    // assignment     → IDENTIFIER ( "=" | "+=" | "-=" | "*=" | "/=" ) assignment | conditional ;
     // recursion cause assignment is right associative. For the other binary operators we loop as
     // long as we match the same operator type because the are left associative
-    fn assignment(&mut self) -> Result<Expr, LoxError> {
+    fn assignment(&mut self) -> Result<ExprId, LoxError> {
         // store Assing Expr in expr
-        let assing_expr = self.parse_or()?;
-        if self.match_token_types(&[Equal]) {
-            let equals = self.previous();
+        let assing_expr = self.conditional()?;
+        if self.match_token_types(&[Equal, PlusEqual, MinusEqual, StarEqual, SlashEqual]) {
+            let equals = self.previous().unwrap().to_token();
             // we call assginement again because we can have var a = 1 = 2 = 3
             let literal_expr = self.assignment()?;
 
-            if let Expr::Variable(var) = &assing_expr {
-                let name = &var.name;
-                return Ok(Expr::Assign(AssignExpr {
-                    name: name.clone(),
-                    value: Box::new(literal_expr),
-                }));
-            }
+            let Expr::Variable(var) = &self.ast[assing_expr] else {
+                return Err(invalid_target(&equals));
+            };
+            let name = var.name.clone();
+            // `a += b` is lowered to `a = a + b`, the target is a variable so reading it twice
+            // can't run anything twice. A plain assignment takes over the node of its target.
+            let Some(token_type) = compound_operator(&equals.token_type) else {
+                self.ast[assing_expr] = Expr::Assign(AssignExpr {
+                    name,
+                    value: literal_expr,
+                    slot: None,
+                });
+                return Ok(assing_expr);
+            };
+            let value = self.ast.add(Expr::Binary(BinaryExpr {
+                left: assing_expr,
+                operator: Token::new(
+                    token_type,
+                    equals.lexeme[..1].to_string(),
+                    None,
+                    equals.line,
+                    equals.column,
+                ),
+                right: literal_expr,
+            }));
+            return Ok(self.ast.add(Expr::Assign(AssignExpr {
+                name,
+                value,
+                slot: None,
+            })));
         }
         Ok(assing_expr)
     }
 
-    fn parse_or(&mut self) -> Result<Expr, LoxError> {
+    // conditional    → logic_or ( "?" expression ":" conditional )? ;
+    // the else branch recurses so `a ? b : c ? d : e` groups as `a ? b : (c ? d : e)`, the `?` and
+    // the `:` delimit the then branch so it can be any expression
+    fn conditional(&mut self) -> Result<ExprId, LoxError> {
+        let condition = self.parse_or()?;
+        if !self.match_token_types(&[Question]) {
+            return Ok(condition);
+        }
+        let operator = self.previous().unwrap().to_token();
+        let then_branch = self.expression()?;
+        self.consume(Colon, "Expect ':' in conditional expression.")?;
+        let else_branch = self.conditional()?;
+        Ok(self.ast.add(Expr::Conditional(ConditionalExpr {
+            condition,
+            operator,
+            then_branch,
+            else_branch,
+        })))
+    }
+
+    fn parse_or(&mut self) -> Result<ExprId, LoxError> {
         let mut expr = self.parse_and()?;
         while self.match_token_types(&[Or]) {
-            let operator = self.previous().unwrap().clone();
+            let operator = self.previous().unwrap().to_token();
             let right = self.parse_and()?;
-            expr = Expr::Logical(LogicalExpr {
-                left: Box::new(expr),
+            expr = self.ast.add(Expr::Logical(LogicalExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            })
+                right,
+            }));
         }
 
         Ok(expr)
     }
 
-    fn parse_and(&mut self) -> Result<Expr, LoxError> {
+    fn parse_and(&mut self) -> Result<ExprId, LoxError> {
         let mut expr = self.equality()?;
         while self.match_token_types(&[And]) {
-            let operator = self.previous().unwrap().clone();
+            let operator = self.previous().unwrap().to_token();
             let right = self.equality()?;
-            expr = Expr::Logical(LogicalExpr {
-                left: Box::new(expr),
+            expr = self.ast.add(Expr::Logical(LogicalExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            })
+                right,
+            }));
         }
 
         Ok(expr)
     }
 
     // equality → comparison ( ( "!=" | "==" ) comparison )* ;
-    fn equality(&mut self) -> Result<Expr, LoxError> {
+    fn equality(&mut self) -> Result<ExprId, LoxError> {
         let mut expr = self.comparison()?;
 
         while self.match_token_types(&[BangEqual, EqualEqual]) {
-            let operator = self.previous().unwrap().clone();
+            let operator = self.previous().unwrap().to_token();
             let right = self.comparison()?;
-            expr = Expr::Binary(BinaryExpr {
-                left: Box::new(expr),
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            });
+                right,
+            }));
         }
 
         Ok(expr)
     }
 
-    // comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
-    fn comparison(&mut self) -> Result<Expr, LoxError> {
-        let mut expr = self.term()?;
+    // comparison     → bit_or ( ( ">" | ">=" | "<" | "<=" ) bit_or )* ;
+    fn comparison(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.bit_or()?;
 
         while self.match_token_types(&[Greater, GreaterEqual, Less, LessEqual]) {
-            let operator = self.previous().unwrap().clone();
+            let operator = self.previous().unwrap().to_token();
+            let right = self.bit_or()?;
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
+                operator,
+                right,
+            }));
+        }
+        Ok(expr)
+    }
+
+    // The bitwise operators bind tighter than comparisons, unlike in C `a & b == 0` tests the
+    // result of `a & b`
+    // bit_or         → bit_xor ( "|" bit_xor )* ;
+    fn bit_or(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.bit_xor()?;
+        while self.match_token_types(&[Pipe]) {
+            let operator = self.previous().unwrap().to_token();
+            let right = self.bit_xor()?;
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
+                operator,
+                right,
+            }));
+        }
+        Ok(expr)
+    }
+
+    // bit_xor        → bit_and ( "^" bit_and )* ;
+    fn bit_xor(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.bit_and()?;
+        while self.match_token_types(&[Caret]) {
+            let operator = self.previous().unwrap().to_token();
+            let right = self.bit_and()?;
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
+                operator,
+                right,
+            }));
+        }
+        Ok(expr)
+    }
+
+    // bit_and        → shift ( "&" shift )* ;
+    fn bit_and(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.shift()?;
+        while self.match_token_types(&[Ampersand]) {
+            let operator = self.previous().unwrap().to_token();
+            let right = self.shift()?;
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
+                operator,
+                right,
+            }));
+        }
+        Ok(expr)
+    }
+
+    // shift          → term ( ( "<<" | ">>" ) term )* ;
+    fn shift(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.term()?;
+        while self.match_token_types(&[LessLess, GreaterGreater]) {
+            let operator = self.previous().unwrap().to_token();
             let right = self.term()?;
-            expr = Expr::Binary(BinaryExpr {
-                left: Box::new(expr),
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            })
+                right,
+            }));
         }
         Ok(expr)
     }
 
     // term           → factor ( ( "-" | "+" ) factor )* ;
-    fn term(&mut self) -> Result<Expr, LoxError> {
+    fn term(&mut self) -> Result<ExprId, LoxError> {
         let mut expr = self.factor()?;
         while self.match_token_types(&[Minus, Plus]) {
-            let operator = self.previous().unwrap().clone();
+            let operator = self.previous().unwrap().to_token();
             let right = self.factor()?;
 
-            expr = Expr::Binary(BinaryExpr {
-                left: Box::new(expr),
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            })
+                right,
+            }));
         }
         Ok(expr)
     }
 
-    // factor         → unary ( ( "/" | "*" ) unary )* ;
-    fn factor(&mut self) -> Result<Expr, LoxError> {
+    // factor         → unary ( ( "/" | "*" | "~/" | "%" ) unary )* ;
+    fn factor(&mut self) -> Result<ExprId, LoxError> {
         let mut expr = self.unary()?;
 
-        while self.match_token_types(&[Slash, Star]) {
-            let operator = self.previous().unwrap().clone();
+        while self.match_token_types(&[Slash, Star, TildeSlash, Percent]) {
+            let operator = self.previous().unwrap().to_token();
             let right = self.unary()?;
 
-            expr = Expr::Binary(BinaryExpr {
-                left: Box::new(expr),
+            expr = self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            })
+                right,
+            }));
         }
         Ok(expr)
     }
 
-    // unary          → ( "!" | "-" ) unary | primary ;
-    fn unary(&mut self) -> Result<Expr, LoxError> {
-        if self.match_token_types(&[Bang, Minus]) {
-            let operator = self.previous().unwrap().clone();
+    // unary          → ( "!" | "-" | "~" | "++" | "--" ) unary | power ;
+    fn unary(&mut self) -> Result<ExprId, LoxError> {
+        if self.match_token_types(&[Bang, Minus, Tilde]) {
+            let operator = self.previous().unwrap().to_token();
+            let right = self.unary()?;
+            return Ok(self.ast.add(Expr::Unary(UnaryExpr { operator, right })));
+        }
+        if self.match_token_types(&[PlusPlus, MinusMinus]) {
+            let operator = self.previous().unwrap().to_token();
+            let target = self.unary()?;
+            return self.update(target, operator, true);
+        }
+
+        self.power()
+    }
+
+    // power          → postfix ( "**" unary )? ;
+    // the right operand is parsed as a unary, that makes `2 ** 3 ** 2` group as `2 ** (3 ** 2)` and
+    // allows `2 ** -1`, while `-2 ** 2` is `-(2 ** 2)`
+    fn power(&mut self) -> Result<ExprId, LoxError> {
+        let expr = self.postfix()?;
+        if self.match_token_types(&[StarStar]) {
+            let operator = self.previous().unwrap().to_token();
             let right = self.unary()?;
-            return Ok(Expr::Unary(UnaryExpr {
+            return Ok(self.ast.add(Expr::Binary(BinaryExpr {
+                left: expr,
                 operator,
-                right: Box::new(right),
-            }));
+                right,
+            })));
         }
+        Ok(expr)
+    }
 
-        Ok(self.call()?)
+    // postfix        → call ( "++" | "--" )* ;
+    fn postfix(&mut self) -> Result<ExprId, LoxError> {
+        let mut expr = self.call()?;
+        while self.match_token_types(&[PlusPlus, MinusMinus]) {
+            let operator = self.previous().unwrap().to_token();
+            expr = self.update(expr, operator, false)?;
+        }
+        Ok(expr)
     }
 
-    fn call(&mut self) -> Result<Expr, LoxError> {
+    fn call(&mut self) -> Result<ExprId, LoxError> {
         let mut expr = self.primary()?;
         loop {
             if self.match_token_types(&[LeftParen]) {
@@ -554,7 +993,7 @@ impl<'a> Parser<'a> {
     }
 
     // TODO: why do we add multiple arguments??
-    fn finish_call(&mut self, callee: Expr) -> Result<Expr, LoxError> {
+    fn finish_call(&mut self, callee: ExprId) -> Result<ExprId, LoxError> {
         let mut arguments = Vec::new();
         if !self.check(&RightParen) {
             loop {
@@ -566,55 +1005,54 @@ impl<'a> Parser<'a> {
                         "Can't have more than 255 arguments",
                     )));
                 }
-                arguments.push(self.expression()?);
+                arguments.push(self.assignment()?);
                 if !self.match_token_types(&[Comma]) {
                     break;
                 }
             }
         }
 
-        let parenthesis = self.consume(RightParen, "Expect ')' after arguments.");
-        Ok(Expr::Call(FunctionCallExpr {
-            callee: Box::new(callee.clone()),
-            paren: parenthesis.unwrap().clone(),
+        let paren = self
+            .consume(RightParen, "Expect ')' after arguments.")?
+            .to_token();
+        Ok(self.ast.add(Expr::Call(FunctionCallExpr {
+            callee,
+            paren,
             arguments,
-        }))
+        })))
     }
 
     // primary        → NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" ;
-    fn primary(&mut self) -> Result<Expr, LoxError> {
+    fn primary(&mut self) -> Result<ExprId, LoxError> {
         if self.match_token_types(&[False]) {
-            return Ok(Expr::Literal(LiteralExpr {
-                value: LoxValue::Boolean(false),
-            }));
+            return Ok(self.literal(LoxValue::Boolean(false)));
         }
         if self.match_token_types(&[True]) {
-            return Ok(Expr::Literal(LiteralExpr {
-                value: LoxValue::Boolean(true),
-            }));
+            return Ok(self.literal(LoxValue::Boolean(true)));
         }
         if self.match_token_types(&[Nil]) {
-            return Ok(Expr::Literal(LiteralExpr {
-                value: LoxValue::Nil,
-            }));
+            return Ok(self.literal(LoxValue::Nil));
+        }
+        if self.match_token_types(&[Interpolation]) {
+            return self.interpolation();
         }
         if self.match_token_types(&[Number, String]) {
-            return Ok(Expr::Literal(LiteralExpr {
-                value: self.previous().unwrap().literal.clone().unwrap(),
-            }));
+            let token = self.previous().unwrap().to_token();
+            return Ok(self.token_literal(token));
         }
 
         if self.match_token_types(&[LeftParen]) {
             let expr = self.expression()?;
             self.consume(RightParen, "expect ')' after expression")?;
-            return Ok(Expr::Grouping(GroupingExpr {
-                expression: Box::new(expr),
-            }));
+            return Ok(self
+                .ast
+                .add(Expr::Grouping(GroupingExpr { expression: expr })));
         }
         if self.match_token_types(&[Identifier]) {
-            Ok(Expr::Variable(VariableExpr {
-                name: self.previous().unwrap().clone(),
-            }))
+            let name = self.previous().unwrap().to_token();
+            Ok(self
+                .ast
+                .add(Expr::Variable(VariableExpr { name, slot: None })))
         }
         // If none of the cases in there match, it means we are sitting on a token that can’t start an expression. We need to handle that error too.
         else {
@@ -626,9 +1064,92 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // interpolation  → INTERPOLATION expression ( INTERPOLATION expression )* STRING ;
+    // `"a ${x} b"` is lowered to `"a " + str(x) + " b"`. The callee is the native itself and not the
+    // variable `str`, so a script can't change what interpolation does.
+    fn interpolation(&mut self) -> Result<ExprId, LoxError> {
+        let first = self.previous().unwrap().to_token();
+        let mut expr = self.token_literal(first);
+        loop {
+            let paren = self.previous().unwrap().to_token();
+            let value = self.expression()?;
+            let callee = self.ast.add(Expr::Literal(LiteralExpr {
+                value: LoxValue::Function(Rc::new(Str {})),
+                token: paren.clone(),
+            }));
+            let stringified = self.ast.add(Expr::Call(FunctionCallExpr {
+                callee,
+                paren: paren.clone(),
+                arguments: vec![value],
+            }));
+            expr = self.concatenate(expr, stringified, &paren);
+
+            if !self.match_token_types(&[Interpolation, String]) {
+                let token = self.peek().unwrap();
+                return Err(LoxError::ParserError(ParserError::new(
+                    token.line,
+                    Loc::Lexeme(token.lexeme.to_owned()),
+                    "Expect '}' after interpolated expression.",
+                )));
+            }
+            let part = self.previous().unwrap().to_token();
+            let last = part.token_type == String;
+            if !part.lexeme.is_empty() {
+                let literal = self.token_literal(part.clone());
+                expr = self.concatenate(expr, literal, &part);
+            }
+            if last {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // `true`, `false` or `nil`, the token that was just consumed
+    fn literal(&mut self, value: LoxValue) -> ExprId {
+        let token = self.previous().unwrap().to_token();
+        self.ast.add(Expr::Literal(LiteralExpr { value, token }))
+    }
+
+    // A number, a string or a piece of an interpolated string, the value was read by the scanner
+    fn token_literal(&mut self, token: Token) -> ExprId {
+        self.ast.add(Expr::Literal(LiteralExpr {
+            value: token.literal.clone().unwrap(),
+            token,
+        }))
+    }
+
+    fn concatenate(&mut self, left: ExprId, right: ExprId, token: &Token) -> ExprId {
+        self.ast.add(Expr::Binary(BinaryExpr {
+            left,
+            operator: Token::new(Plus, "+".to_string(), None, token.line, token.column),
+            right,
+        }))
+    }
+
+    // `++` and `--` only apply to variables, the variable becomes the update
+    fn update(
+        &mut self,
+        target: ExprId,
+        operator: Token,
+        prefix: bool,
+    ) -> Result<ExprId, LoxError> {
+        match &self.ast[target] {
+            Expr::Variable(var) => {
+                self.ast[target] = Expr::Update(UpdateExpr {
+                    name: var.name.clone(),
+                    operator,
+                    prefix,
+                    slot: None,
+                });
+                Ok(target)
+            }
+            _ => Err(invalid_target(&operator)),
+        }
+    }
+
     // returning the just consumed token makes it easier to use match_token_types
-    fn previous(&self) -> Option<&Token> {
-        self.tokens.get(self.current - 1)
+    fn previous(&self) -> Option<&TokenRef<'a>> {
+        self.previous.as_ref()
     }
 
     fn match_token_types(&mut self, token_types: &[TokenType]) -> bool {
@@ -650,23 +1171,43 @@ impl<'a> Parser<'a> {
         self.peek().unwrap().token_type == *ttype
     }
 
-    fn peek(&self) -> Option<Token> {
-        self.tokens.get(self.current).clone().cloned()
+    fn peek(&self) -> Option<&TokenRef<'a>> {
+        Some(&self.current)
     }
 
-    fn advance(&mut self) -> Token {
+    fn advance(&mut self) -> &TokenRef<'a> {
         if !self.is_at_end() {
-            self.current += 1
+            let next = self.next_token();
+            self.previous = Some(std::mem::replace(&mut self.current, next));
+        }
+        self.previous().unwrap()
+    }
+
+    // The token after `current`, an `Eof` once the scanner failed
+    fn next_token(&mut self) -> TokenRef<'a> {
+        match self.tokens.next() {
+            Some(Ok(token)) => token,
+            Some(Err(error)) => {
+                let line = match &error {
+                    LoxError::ScannerError(e) => e.line(),
+                    _ => self.current.line,
+                };
+                self.scan_error = Some(error);
+                end_of_file(line)
+            }
+            None => end_of_file(self.current.line),
         }
-        self.previous().unwrap().clone()
     }
 
     fn is_at_end(&self) -> bool {
         self.peek().unwrap().token_type == Eof
     }
 
-    // We clone token to take ownershiop of the value and avoid borrower issues later on
-    fn consume(&mut self, ttype: TokenType, error_message: &str) -> Result<Token, LoxError> {
+    fn consume(
+        &mut self,
+        ttype: TokenType,
+        error_message: &str,
+    ) -> Result<&TokenRef<'a>, LoxError> {
         if self.check(&ttype) {
             return Ok(self.advance());
         }
@@ -679,45 +1220,29 @@ impl<'a> Parser<'a> {
         )))
     }
 
-    fn synchronize(&mut self) -> Result<(), LoxError> {
+    // discard tokens until we are at the start of the next statement, so one syntax error doesn't
+    // cascade into a list of bogus follow-up errors
+    fn synchronize(&mut self) {
         self.advance();
         while !self.is_at_end() {
             if self.previous().unwrap().token_type == Semicolon {
-                return Ok(());
+                return;
             }
             match self.peek().unwrap().token_type {
-                TokenType::Class => {
-                    // Implement the logic for handling 'Class'
-                }
-                TokenType::Fun => {
-                    // Implement the logic for handling 'Fun'
+                Class | Fun | Var | For | If | While | Print | Return | Import | Throw | Try => {
+                    return
                 }
-                TokenType::Var => {
-                    // Implement the logic for handling 'Var'
+                _ => {
+                    self.advance();
                 }
-                TokenType::For => {
-                    // Implement the logic for handling 'For'
-                }
-                TokenType::If => {
-                    // Implement the logic for handling 'If'
-                }
-                TokenType::While => {
-                    // Implement the logic for handling 'While'
-                }
-                TokenType::Print => {
-                    // Implement the logic for handling 'Print'
-                }
-                TokenType::Return => {
-                    // Implement the logic for handling 'Return'
-                }
-                _ => unreachable!(), // Marking other cases as unreachable
             }
-            self.advance();
         }
-        Ok(())
     }
 
     fn parse_for_statement(&mut self) -> Result<Stmt, LoxError> {
+        // the desugared statements all report the line of the `for` keyword
+        let keyword = self.previous().unwrap().to_token();
+        let line = keyword.line;
         let _ = self.consume(LeftParen, "Expect '(' after 'for'.");
         // parse intializer of for loop
         let initializer: Option<Stmt>;
@@ -729,7 +1254,7 @@ impl<'a> Parser<'a> {
             initializer = Some(self.expression_statement()?);
         }
         // parse loop condition
-        let mut condition: Option<Expr> = None;
+        let mut condition: Option<ExprId> = None;
         if !self.check(&Semicolon) {
             condition = Some(self.expression()?);
         }
@@ -737,7 +1262,7 @@ impl<'a> Parser<'a> {
 
         // parse increment
 
-        let mut increment: Option<Expr> = None;
+        let mut increment: Option<ExprId> = None;
         if !self.check(&RightParen) {
             increment = Some(self.expression()?);
         }
@@ -747,26 +1272,66 @@ impl<'a> Parser<'a> {
 
         if let Some(inc) = increment {
             body = Stmt::Block(BlockStmt {
-                statements: vec![body, Stmt::Expression(ExpressionStmt { expression: inc })],
+                statements: vec![
+                    body,
+                    Stmt::Expression(ExpressionStmt {
+                        expression: inc,
+                        line,
+                    }),
+                ],
+                line,
             })
         }
 
-        if let None = condition {
-            condition = Some(Expr::Literal(LiteralExpr {
+        if condition.is_none() {
+            condition = Some(self.ast.add(Expr::Literal(LiteralExpr {
                 value: LoxValue::Boolean(true),
-            }));
+                token: keyword,
+            })));
         }
         body = Stmt::While(WhileStmt {
             condition: condition.expect("A condition should be present!"),
             body: Box::new(body),
+            line,
         });
 
         if let Some(initializer) = initializer {
             body = Stmt::Block(BlockStmt {
                 statements: vec![initializer, body],
+                line,
             })
         }
 
         Ok(body)
     }
 }
+
+// Stands in for the tokens after the end of the source, or after a scanner error
+fn end_of_file<'a>(line: usize) -> TokenRef<'a> {
+    TokenRef {
+        token_type: Eof,
+        lexeme: "",
+        literal: None,
+        line,
+        column: 0,
+    }
+}
+
+// The binary operator a compound assignment applies, None for a plain `=`
+fn compound_operator(token_type: &TokenType) -> Option<TokenType> {
+    match token_type {
+        PlusEqual => Some(Plus),
+        MinusEqual => Some(Minus),
+        StarEqual => Some(Star),
+        SlashEqual => Some(Slash),
+        _ => None,
+    }
+}
+
+fn invalid_target(operator: &Token) -> LoxError {
+    LoxError::ParserError(ParserError::new(
+        operator.line,
+        Loc::Lexeme(operator.lexeme.to_owned()),
+        "Invalid assignment target.",
+    ))
+}
@@ -0,0 +1,203 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    // time from entering to leaving the function, nested recursive calls are only counted once
+    pub inclusive: Duration,
+    // time spent in the function body itself, without the functions it called
+    pub exclusive: Duration,
+}
+
+// Everything recorded while a script ran with the profiler enabled
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    // sorted by exclusive time, the most expensive function first
+    pub functions: Vec<FunctionProfile>,
+    // how often a statement on the line was executed
+    pub lines: BTreeMap<usize, u64>,
+    // the same for the lines of every imported module
+    pub module_lines: BTreeMap<PathBuf, BTreeMap<usize, u64>>,
+    // `<script>;caller;callee` -> exclusive time spent with exactly this stack
+    pub stacks: BTreeMap<String, Duration>,
+}
+
+impl Profile {
+    pub fn function(&self, name: &str) -> Option<&FunctionProfile> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    // Human readable summary, functions by exclusive time and the hottest lines
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let _ = writeln!(
+            report,
+            "{:<24} {:>10} {:>16} {:>16}",
+            "function", "calls", "inclusive (ms)", "exclusive (ms)"
+        );
+        for function in &self.functions {
+            let _ = writeln!(
+                report,
+                "{:<24} {:>10} {:>16.3} {:>16.3}",
+                function.name,
+                function.calls,
+                milliseconds(function.inclusive),
+                milliseconds(function.exclusive)
+            );
+        }
+
+        // a line of a module shows up as `module.lox:3`
+        let mut lines: Vec<(String, u64)> = self
+            .lines
+            .iter()
+            .map(|(line, hits)| (line.to_string(), *hits))
+            .collect();
+        for (module, module_lines) in &self.module_lines {
+            let name = module.file_name().unwrap_or_default().to_string_lossy();
+            for (line, hits) in module_lines {
+                lines.push((format!("{}:{}", name, line), *hits));
+            }
+        }
+        lines.sort_by_key(|(_, hits)| Reverse(*hits));
+        let _ = writeln!(report, "\n{:<8} {:>10}", "line", "hits");
+        for (line, hits) in lines {
+            let _ = writeln!(report, "{:<8} {:>10}", line, hits);
+        }
+        report
+    }
+
+    // One `frame;frame;frame microseconds` line per stack, the input format of flamegraph.pl
+    pub fn folded_stacks(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|(stack, time)| format!("{} {}\n", stack, time.as_micros()))
+            .collect()
+    }
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+struct ActiveCall {
+    name: String,
+    started: Instant,
+    // inclusive time of the calls made from this one
+    children: Duration,
+}
+
+// Collects timings while the interpreter runs, the interpreter reports calls and lines to it
+pub struct Profiler {
+    started: Instant,
+    calls: Vec<ActiveCall>,
+    functions: HashMap<String, FunctionProfile>,
+    lines: BTreeMap<usize, u64>,
+    module_lines: BTreeMap<PathBuf, BTreeMap<usize, u64>>,
+    stacks: BTreeMap<String, Duration>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            started: Instant::now(),
+            calls: Vec::new(),
+            functions: HashMap::new(),
+            lines: BTreeMap::new(),
+            module_lines: BTreeMap::new(),
+            stacks: BTreeMap::new(),
+        }
+    }
+
+    // `module` is the file of an imported module, `None` for the script
+    pub fn on_line(&mut self, module: Option<&Path>, line: usize) {
+        let lines = match module {
+            None => &mut self.lines,
+            Some(module) => match self.module_lines.get_mut(module) {
+                Some(lines) => lines,
+                None => self.module_lines.entry(module.to_path_buf()).or_default(),
+            },
+        };
+        *lines.entry(line).or_default() += 1;
+    }
+
+    pub fn enter(&mut self, name: &str) {
+        self.calls.push(ActiveCall {
+            name: name.to_string(),
+            started: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    pub fn exit(&mut self) {
+        let Some(call) = self.calls.pop() else {
+            return;
+        };
+        let elapsed = call.started.elapsed();
+        let exclusive = elapsed.saturating_sub(call.children);
+        if let Some(caller) = self.calls.last_mut() {
+            caller.children += elapsed;
+        }
+
+        let stack = self.stack_key(&call.name);
+        *self.stacks.entry(stack).or_default() += exclusive;
+
+        let recursive = self.calls.iter().any(|active| active.name == call.name);
+        let function = self
+            .functions
+            .entry(call.name.clone())
+            .or_insert_with(|| FunctionProfile {
+                name: call.name.clone(),
+                ..FunctionProfile::default()
+            });
+        function.calls += 1;
+        function.exclusive += exclusive;
+        // the outermost call already covers the time of the recursive ones
+        if !recursive {
+            function.inclusive += elapsed;
+        }
+    }
+
+    fn stack_key(&self, name: &str) -> String {
+        let mut stack = String::from("<script>");
+        for call in &self.calls {
+            stack.push(';');
+            stack.push_str(&call.name);
+        }
+        stack.push(';');
+        stack.push_str(name);
+        stack
+    }
+
+    // Stop profiling, calls that are still running (the script failed) are closed now
+    pub fn finish(mut self) -> Profile {
+        while !self.calls.is_empty() {
+            self.exit();
+        }
+
+        // whatever the functions didn't use was spent at the top level of the script
+        let total = self.started.elapsed();
+        let in_functions: Duration = self.stacks.values().sum();
+        self.stacks
+            .insert("<script>".to_string(), total.saturating_sub(in_functions));
+
+        let mut functions: Vec<FunctionProfile> = self.functions.into_values().collect();
+        functions.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then(a.name.cmp(&b.name)));
+        Profile {
+            functions,
+            lines: self.lines,
+            module_lines: self.module_lines,
+            stacks: self.stacks,
+        }
+    }
+}
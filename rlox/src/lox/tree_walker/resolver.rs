@@ -0,0 +1,202 @@
+use crate::frontend::symbol::Symbol;
+use crate::frontend::token::Token;
+
+use super::parser::{Ast, Expr, ExprId, FunctionDecl, Stmt};
+
+// Where a local variable lives: how many environments up from the current one, and its index in
+// the slots of that environment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
+// A local scope the way the interpreter will create it, names in the order they get declared
+#[derive(Default)]
+struct Scope {
+    names: Vec<Symbol>,
+    // an `import` without a list of names binds whatever the module exports, nothing about this
+    // scope can be known before it runs
+    dynamic: bool,
+}
+
+impl Scope {
+    fn declare(&mut self, name: Symbol) {
+        // declaring the same name again reuses its slot, like the interpreter does
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+    }
+}
+
+// Walks the program before it runs and works out the slot of every local variable. The slot is
+// stored in the variable, assignment or update expression. Globals and everything else that can
+// only be known at runtime have no slot and are looked up by name.
+pub struct Resolver<'a> {
+    ast: &'a mut Ast,
+    scopes: Vec<Scope>,
+    // functions don't close over the scopes around them, only their own scopes are visible in
+    // their body
+    function_start: usize,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(ast: &'a mut Ast) -> Self {
+        Resolver {
+            ast,
+            scopes: Vec::new(),
+            function_start: 0,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Expression(stmt) => self.resolve_expression(stmt.expression),
+            Stmt::Print(stmt) => self.resolve_expression(stmt.expression),
+            Stmt::Function(fun) => {
+                self.declare(&fun.name);
+                self.resolve_function(fun);
+            }
+            Stmt::Var(stmt) => {
+                if let Some(initializer) = stmt.initializer {
+                    self.resolve_expression(initializer);
+                }
+                self.declare(&stmt.name);
+            }
+            Stmt::If(stmt) => {
+                self.resolve_expression(stmt.condition);
+                self.resolve_statement(&stmt.then_branch);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::Return(stmt) => {
+                if let Some(value) = stmt.value {
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Block(stmt) => self.resolve_block(&stmt.statements, None),
+            Stmt::While(stmt) => {
+                self.resolve_expression(stmt.condition);
+                self.resolve_statement(&stmt.body);
+            }
+            Stmt::Import(stmt) => match &stmt.names {
+                Some(names) => names.iter().for_each(|name| self.declare(name)),
+                None => {
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.dynamic = true;
+                    }
+                }
+            },
+            Stmt::Throw(stmt) => self.resolve_expression(stmt.value),
+            Stmt::Try(stmt) => {
+                self.resolve_block(&stmt.body, None);
+                if let Some(catch) = &stmt.catch {
+                    self.resolve_block(&catch.body, Some(&catch.name));
+                }
+                if let Some(finally) = &stmt.finally {
+                    self.resolve_block(finally, None);
+                }
+            }
+        }
+    }
+
+    // The statements run in a new environment, a catch block starts with the caught value in it
+    fn resolve_block(&mut self, statements: &[Stmt], caught: Option<&Token>) {
+        self.scopes.push(Scope::default());
+        if let Some(name) = caught {
+            self.declare(name);
+        }
+        self.resolve(statements);
+        self.scopes.pop();
+    }
+
+    // The parameters and the body share the environment of the call
+    fn resolve_function(&mut self, fun: &FunctionDecl) {
+        let function_start = std::mem::replace(&mut self.function_start, self.scopes.len());
+        self.scopes.push(Scope::default());
+        for parameter in &fun.parameters {
+            self.declare(parameter);
+        }
+        self.resolve(&fun.body);
+        self.scopes.pop();
+        self.function_start = function_start;
+    }
+
+    fn resolve_expression(&mut self, id: ExprId) {
+        match &self.ast[id] {
+            Expr::Assign(expr) => {
+                let value = expr.value;
+                self.resolve_expression(value);
+                self.resolve_local(id);
+            }
+            Expr::Binary(expr) => {
+                let (left, right) = (expr.left, expr.right);
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expr::Logical(expr) => {
+                let (left, right) = (expr.left, expr.right);
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expr::Comma(expr) => {
+                let (left, right) = (expr.left, expr.right);
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expr::Conditional(expr) => {
+                let branches = [expr.condition, expr.then_branch, expr.else_branch];
+                branches
+                    .into_iter()
+                    .for_each(|id| self.resolve_expression(id));
+            }
+            Expr::Call(expr) => {
+                let callee = expr.callee;
+                let arguments = expr.arguments.clone();
+                self.resolve_expression(callee);
+                arguments
+                    .into_iter()
+                    .for_each(|id| self.resolve_expression(id));
+            }
+            Expr::Grouping(expr) => self.resolve_expression(expr.expression),
+            Expr::Unary(expr) => self.resolve_expression(expr.right),
+            Expr::Update(_) | Expr::Variable(_) => self.resolve_local(id),
+            Expr::Literal(_) => (),
+        }
+    }
+
+    // Top level declarations are globals, they keep being looked up by name
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.declare(name.symbol());
+        }
+    }
+
+    fn resolve_local(&mut self, id: ExprId) {
+        let (name, slot) = match &mut self.ast[id] {
+            Expr::Assign(expr) => (&expr.name, &mut expr.slot),
+            Expr::Update(expr) => (&expr.name, &mut expr.slot),
+            Expr::Variable(expr) => (&expr.name, &mut expr.slot),
+            _ => return,
+        };
+        let symbol = name.symbol();
+        let visible = &self.scopes[self.function_start..];
+        for (depth, scope) in visible.iter().rev().enumerate() {
+            // the import may have bound the name, or shifted the slots of this scope
+            if scope.dynamic {
+                return;
+            }
+            if let Some(index) = scope.names.iter().position(|name| *name == symbol) {
+                *slot = Some(Slot { depth, index });
+                return;
+            }
+        }
+    }
+}
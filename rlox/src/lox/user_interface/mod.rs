@@ -1,81 +1,319 @@
+pub mod repl;
+pub mod terminal_debugger;
+
 use crate::frontend::scanner::Scanner;
+use crate::json::{ast_to_json, tokens_to_json};
+use crate::lint::{lint, LintConfig};
+use crate::tree_walker::arithmetic::NumericPolicy;
+use crate::tree_walker::ast_printer::to_tree;
+use crate::tree_walker::coverage::Coverage;
 use crate::tree_walker::interpreter::Interpreter;
-use crate::tree_walker::parser::{Parser, Stmt};
+use crate::tree_walker::optimizer::{optimize, OptLevel};
+use crate::tree_walker::parser::{Parser, Program};
 use crate::LoxError;
-use std::io::{BufRead, Write};
+use std::path::Path;
 use std::{fs, io, process};
+use repl::{
+    history_path, LoxHelper, OpenDelimiters, ReplSession, CONTINUATION_PROMPT, PROMPT,
+};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+use serde_json::Value;
+use terminal_debugger::TerminalDebugger;
+
+// What `lox` prints instead of running the script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dump {
+    Tokens,
+    Ast,
+    TokensJson,
+    AstJson,
+}
 
 pub fn run_file(file_path: &str) -> Result<(), io::Error> {
+    let contents = fs::read_to_string(file_path)?;
+    run_script(
+        &contents,
+        Some(Path::new(file_path)),
+        Vec::new(),
+        OptLevel::default(),
+        NumericPolicy::default(),
+    );
+    Ok(())
+}
+
+// Run a script read from a file, `-e` or stdin, `args` are what follows it on the command line.
+// `path` is where the script came from, imports are resolved relative to it. An error ends the
+// process with its exit code.
+pub fn run_script(
+    source: &str,
+    path: Option<&Path>,
+    args: Vec<String>,
+    opt_level: OptLevel,
+    numeric_policy: NumericPolicy,
+) {
     // initialize the interpreter, which contains the environment field, so that we can hold on to the state of the program one we run it
     let mut interpreter = Interpreter::new();
+    interpreter.set_args(args);
+    interpreter.set_opt_level(opt_level);
+    interpreter.set_numeric_policy(numeric_policy);
+    if let Some(path) = path {
+        interpreter.set_script_path(path);
+    }
+
+    if let Err(e) = run(source, &mut interpreter) {
+        exit_with_error(e);
+    }
+}
+
+// The syntax tree is shown the way it runs at `opt_level`
+pub fn dump_script(source: &str, dump: Dump, opt_level: OptLevel) {
+    let program = || parse(source).map(|program| optimize(program, opt_level));
+    let output = match dump {
+        Dump::Tokens => dump_tokens(source),
+        Dump::Ast => program().map(|program| to_tree(&program).trim_end().to_string()),
+        Dump::TokensJson => dump_tokens_json(source).map(|json| json.to_string()),
+        Dump::AstJson => program().map(|program| ast_to_json(&program).to_string()),
+    };
+    match output {
+        Ok(output) => println!("{}", output),
+        Err(e) => exit_with_error(e),
+    }
+}
+
+// One `line:column TokenType 'lexeme'` per token
+pub fn dump_tokens(source: &str) -> Result<String, LoxError> {
+    let lines = Scanner::build_scanner(source)
+        .map(|token| {
+            token.map(|token| {
+                format!(
+                    "{}:{} {:?} '{}'",
+                    token.line, token.column, token.token_type, token.lexeme
+                )
+            })
+        })
+        .collect::<Result<Vec<String>, LoxError>>()?;
+    Ok(lines.join("\n"))
+}
+
+pub fn dump_tokens_json(source: &str) -> Result<Value, LoxError> {
+    let tokens = Scanner::build_scanner(source).scan_tokens()?;
+    Ok(tokens_to_json(&tokens))
+}
 
+pub fn dump_ast_json(source: &str) -> Result<Value, LoxError> {
+    Ok(ast_to_json(&parse(source)?))
+}
+
+pub fn dump_ast(source: &str) -> Result<String, LoxError> {
+    let program = parse(source)?;
+    Ok(to_tree(&program).trim_end().to_string())
+}
+
+// Statically check a script without running it, returns the number of warnings that were printed
+pub fn run_lint(file_path: &str, config: &LintConfig) -> Result<usize, io::Error> {
     let contents = fs::read_to_string(file_path)?;
 
-    if let Err(e) = run(&contents, &mut interpreter) {
-        match e {
-            LoxError::ScannerError(e) => {
-                e.report();
-                process::exit(65)
-            }
-            LoxError::ParserError(e) => {
-                e.report();
-                process::exit(66)
+    let program = match parse(&contents) {
+        Ok(program) => program,
+        Err(e) => exit_with_error(e),
+    };
+
+    let warnings = lint(&program, config);
+    for warning in &warnings {
+        println!("{}: {}", file_path, warning);
+    }
+    Ok(warnings.len())
+}
+
+// Run a script under the terminal debugger, it pauses before the first statement
+pub fn run_debugger(file_path: &str) -> Result<(), io::Error> {
+    let contents = fs::read_to_string(file_path)?;
+    let program = match parse(&contents) {
+        Ok(program) => program,
+        Err(e) => exit_with_error(e),
+    };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_path(Path::new(file_path));
+    interpreter.attach_debugger(Box::new(TerminalDebugger::stdio(&contents)), true);
+    if let Err(e) = interpreter.interpret(program) {
+        if interpreter.is_terminated() {
+            return Ok(());
+        }
+        exit_with_error(e);
+    }
+    Ok(())
+}
+
+// Run a script with the profiler, the report goes to stderr so it doesn't mix with the output of
+// the script. The folded stacks are written to `folded_path` for flamegraph tools.
+pub fn run_profiler(file_path: &str, folded_path: Option<&str>) -> Result<(), io::Error> {
+    let contents = fs::read_to_string(file_path)?;
+    let program = match parse(&contents) {
+        Ok(program) => program,
+        Err(e) => exit_with_error(e),
+    };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script_path(Path::new(file_path));
+    interpreter.enable_profiler();
+    let result = interpreter.interpret(program);
+
+    // a failing script still gets its profile, it shows how far it came
+    if let Some(profile) = interpreter.take_profile() {
+        eprint!("{}", profile.report());
+        if let Some(folded_path) = folded_path {
+            fs::write(folded_path, profile.folded_stacks())?;
+        }
+    }
+    if let Err(e) = result {
+        exit_with_error(e);
+    }
+    Ok(())
+}
+
+// Run every script with coverage enabled, prints a summary per script and optionally writes all
+// of them into one LCOV file. Returns the exit code of the first script that failed, 0 when
+// they all ran through.
+pub fn run_coverage(file_paths: &[String], lcov_path: Option<&str>) -> Result<i32, io::Error> {
+    let mut lcov = String::new();
+    let mut exit_code = 0;
+
+    for file_path in file_paths {
+        let contents = fs::read_to_string(file_path)?;
+        // a script that fails, to parse or to run, doesn't stop the others from being measured
+        let program = match parse(&contents) {
+            Ok(program) => program,
+            Err(e) => {
+                report_in_file(file_path, &e);
+                if exit_code == 0 {
+                    exit_code = e.exit_code();
+                }
+                continue;
             }
-            LoxError::Interpreter(e) => {
-                e.report();
-                process::exit(70)
+        };
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_script_path(Path::new(file_path));
+        interpreter.enable_coverage(Coverage::new(&program.statements));
+        if let Err(e) = interpreter.interpret(program) {
+            report_in_file(file_path, &e);
+            if exit_code == 0 {
+                exit_code = e.exit_code();
             }
-            LoxError::Runtime(e) => {
-                e.report();
-                process::exit(1)
+        }
+
+        if let Some(coverage) = interpreter.take_coverage() {
+            eprintln!("{}", coverage.summary(file_path));
+            lcov.push_str(&coverage.lcov(file_path));
+            for (module, coverage) in &coverage.modules {
+                let module = module.display().to_string();
+                eprintln!("{}", coverage.summary(&module));
+                lcov.push_str(&coverage.lcov(&module));
             }
-            LoxError::Return(_) => todo!(),
         }
     }
-    Ok(())
+
+    if let Some(lcov_path) = lcov_path {
+        fs::write(lcov_path, lcov)?;
+    }
+    Ok(exit_code)
+}
+
+fn report_in_file(file_path: &str, error: &LoxError) {
+    eprint!("{}: ", file_path);
+    error.report();
+}
+
+fn exit_with_error(error: LoxError) -> ! {
+    error.report();
+    process::exit(error.exit_code())
 }
 
 // REPL: print eval read -> interactive prompt
 pub fn run_prompt() -> Result<(), io::Error> {
-    // initialize the interpreter, which contains the environment field, so that we can hold on to the state of the program one we run it
-    let mut interpreter = Interpreter::new();
+    // the session holds the interpreter, so the state of the program survives between entries
+    let mut session = ReplSession::default();
 
-    let stdin = io::stdin();
-    let mut reader = io::BufReader::new(stdin.lock());
+    let mut editor: Editor<LoxHelper, DefaultHistory> =
+        Editor::new().map_err(io::Error::other)?;
+    editor.set_helper(Some(LoxHelper::default()));
+    let history = history_path();
+    if let Some(path) = &history {
+        // there is no history yet on the first run
+        let _ = editor.load_history(path);
+    }
 
     loop {
-        print!("> ");
-        io::stdout().flush()?; // Ensure the prompt is displayed.
-
-        let mut buf = String::new();
-        reader.read_line(&mut buf)?;
+        if let Some(helper) = editor.helper_mut() {
+            helper.globals = session
+                .interpreter
+                .globals
+                .borrow()
+                .bindings()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+        }
 
-        if buf.is_empty() {
-            // Check for an empty line and break the loop if encountered.
+        let Some(buf) = read_entry(&mut editor)? else {
             break;
+        };
+        if buf.trim().is_empty() {
+            continue;
         }
+        let _ = editor.add_history_entry(buf.trim_end());
 
-        if let Err(e) = run(&buf, &mut interpreter) {
-            match e {
-                LoxError::Interpreter(e) => e.report(),
-                LoxError::ParserError(e) => e.report(),
-                LoxError::ScannerError(e) => e.report(),
-                LoxError::Runtime(e) => e.report(),
-                LoxError::Return(_) => todo!(),
-            }
+        match session.eval(&buf) {
+            Ok(output) if output.is_empty() => (),
+            Ok(output) => println!("{}", output),
+            Err(e) => e.report(),
         }
     }
 
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
     Ok(())
 }
 
+// Read lines until braces, parens and strings are balanced. None once the user is done (Ctrl-D),
+// Ctrl-C throws away the entry that is being typed.
+fn read_entry(editor: &mut Editor<LoxHelper, DefaultHistory>) -> Result<Option<String>, io::Error> {
+    let mut entry = String::new();
+    let mut delimiters = OpenDelimiters::default();
+    loop {
+        let prompt = if entry.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                let start = entry.len();
+                entry.push_str(&line);
+                entry.push('\n');
+                delimiters.feed(&entry[start..]);
+                if !delimiters.is_incomplete() {
+                    return Ok(Some(entry));
+                }
+            }
+            Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => return Ok(None),
+            Err(e) => return Err(io::Error::other(e)),
+        }
+    }
+}
+
 // run shouldn't be pub but for the moment I'm using it in my integration tests
-pub fn run(source: &String, interpreter: &mut Interpreter) -> Result<(), LoxError> {
-    let mut scanner = Scanner::build_scanner(source);
-    let tokens = scanner.scan_tokens()?;
-    let binding = tokens.clone();
-    let mut parser = Parser::build_parser(&binding);
-    let statements: Vec<Stmt> = parser.parse()?;
-    interpreter.interpret(statements)?;
+pub fn run(source: &str, interpreter: &mut Interpreter) -> Result<(), LoxError> {
+    let program = parse(source)?;
+    interpreter.interpret(program)?;
     Ok(())
 }
+
+pub fn parse(source: &str) -> Result<Program, LoxError> {
+    Parser::from_source(source).parse()
+}
@@ -0,0 +1,274 @@
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+use crate::frontend::lox_value::LoxValue;
+use crate::frontend::scanner::keywords;
+use crate::tree_walker::ast_printer::expr_to_sexpr;
+use crate::tree_walker::interpreter::Interpreter;
+use crate::tree_walker::parser::Parser;
+use crate::{LoxError, RuntimeError};
+
+use super::{dump_tokens, run};
+
+pub const PROMPT: &str = "> ";
+pub const CONTINUATION_PROMPT: &str = "... ";
+
+const HELP: &str = "\
+Type statements, or an expression to see its value.
+Commands:
+  :env            show the global variables
+  :load <file>    run a script in this session
+  :reset          forget every variable and function
+  :ast <expr>     show how an expression is parsed
+  :tokens <src>   show the tokens of the source
+  :help           show this help";
+
+// State of an interactive session, every entry runs against the same interpreter
+#[derive(Default)]
+pub struct ReplSession {
+    pub interpreter: Interpreter,
+}
+
+impl ReplSession {
+    // Run an entry and return what should be shown to the user, errors are left to the caller.
+    // A panic (a bug in a native function) is turned into an error and the session stays usable.
+    pub fn eval(&mut self, entry: &str) -> Result<String, LoxError> {
+        // the panic is reported as that error, the default hook would print it a second time
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.eval_entry(entry)));
+        panic::set_hook(hook);
+
+        match result {
+            Ok(result) => result,
+            Err(payload) => {
+                self.interpreter.recover();
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(LoxError::Runtime(RuntimeError::throw(format!(
+                    "Internal error: {}",
+                    message
+                ))))
+            }
+        }
+    }
+
+    fn eval_entry(&mut self, entry: &str) -> Result<String, LoxError> {
+        let entry = entry.trim();
+        if let Some(command) = entry.strip_prefix(':') {
+            return self.meta_command(command);
+        }
+
+        // `1 + 2` or `x = 3;` echo their value, anything that isn't an expression is run as
+        // statements and reports its own errors
+        let expression = entry.strip_suffix(';').unwrap_or(entry);
+        let top = self.interpreter.call_stack().len() - 1;
+        match self.interpreter.evaluate_in_frame(expression, top) {
+            Ok(value) => Ok(echo(&value)),
+            Err(LoxError::ParserError(_) | LoxError::ScannerError(_)) => {
+                run(entry, &mut self.interpreter)?;
+                Ok(String::new())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn meta_command(&mut self, command: &str) -> Result<String, LoxError> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "env" => Ok(self
+                .interpreter
+                .globals
+                .borrow()
+                .bindings()
+                .iter()
+                .map(|(name, value)| format!("{} = {}", name, echo(value)))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            "load" => {
+                let source = fs::read_to_string(argument).map_err(|e| {
+                    LoxError::Runtime(RuntimeError::throw(format!(
+                        "Can't load '{}': {}",
+                        argument, e
+                    )))
+                })?;
+                run(&source, &mut self.interpreter)?;
+                Ok(format!("Loaded {}", argument))
+            }
+            "reset" => {
+                self.interpreter = Interpreter::new();
+                Ok("Session reset".to_string())
+            }
+            "ast" => {
+                let (ast, expression) = Parser::from_source(argument).parse_expression()?;
+                Ok(expr_to_sexpr(&ast, expression))
+            }
+            "tokens" => dump_tokens(argument),
+            "help" | "h" => Ok(HELP.to_string()),
+            _ => Err(LoxError::Runtime(RuntimeError::throw(format!(
+                "Unknown command ':{}', type :help",
+                name
+            )))),
+        }
+    }
+}
+
+// Strings are quoted so `"1"` and `1` can be told apart
+fn echo(value: &LoxValue) -> String {
+    match value {
+        LoxValue::String(s) => format!("{:?}", s),
+        LoxValue::Function(fun) => format!("<fn {}>", fun.name()),
+        _ => value.as_str(),
+    }
+}
+
+// The input can't be parsed yet because a brace, paren or string is still open. Closing more than
+// was opened is a real error, the parser reports it.
+pub fn is_incomplete(source: &str) -> bool {
+    let mut delimiters = OpenDelimiters::default();
+    delimiters.feed(source);
+    delimiters.is_incomplete()
+}
+
+// What is still open in the entry that is being typed. Lines are fed as they come in, so a long
+// entry isn't scanned again from the start for every line that gets added to it. Only this check
+// is incremental, the finished entry is scanned and parsed as a whole.
+#[derive(Debug, Default)]
+pub struct OpenDelimiters {
+    depth: i64,
+    mode: Mode,
+}
+
+#[derive(Debug, Default, PartialEq)]
+enum Mode {
+    #[default]
+    Code,
+    String,
+    // right after a backslash inside of a string
+    Escape,
+    Comment,
+    // inside of `/* */`, nested as deep as the number says
+    BlockComment(usize),
+}
+
+impl OpenDelimiters {
+    pub fn feed(&mut self, input: &str) {
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            self.mode = match (&self.mode, c) {
+                (Mode::Code, '(' | '{') => {
+                    self.depth += 1;
+                    Mode::Code
+                }
+                (Mode::Code, ')' | '}') => {
+                    self.depth -= 1;
+                    Mode::Code
+                }
+                (Mode::Code, '"') => Mode::String,
+                (Mode::Code, '/') if chars.peek() == Some(&'/') => Mode::Comment,
+                (Mode::Code, '/') if chars.next_if_eq(&'*').is_some() => Mode::BlockComment(1),
+                (Mode::BlockComment(depth), '/') if chars.next_if_eq(&'*').is_some() => {
+                    Mode::BlockComment(depth + 1)
+                }
+                (Mode::BlockComment(1), '*') if chars.next_if_eq(&'/').is_some() => Mode::Code,
+                (Mode::BlockComment(depth), '*') if chars.next_if_eq(&'/').is_some() => {
+                    Mode::BlockComment(depth - 1)
+                }
+                (Mode::String, '"') | (Mode::Comment, '\n') => Mode::Code,
+                (Mode::String, '\\') => Mode::Escape,
+                (Mode::Escape, _) => Mode::String,
+                _ => continue,
+            };
+        }
+    }
+
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self.mode,
+            Mode::String | Mode::Escape | Mode::BlockComment(_)
+        ) || self.depth > 0
+    }
+}
+
+// Completions for the identifier that ends at `pos`, returns where it starts and the candidates
+pub fn complete(line: &str, pos: usize, globals: &[String]) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(pos, |(index, _)| index);
+    let prefix = &line[start..pos];
+    if prefix.is_empty() {
+        return (pos, Vec::new());
+    }
+
+    let mut candidates: Vec<String> = keywords()
+        .map(String::from)
+        .chain(globals.iter().cloned())
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+// History survives between sessions, `LOX_HISTORY` overrides the default `~/.lox_history`
+pub fn history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("LOX_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".lox_history"))
+}
+
+// Line editor helper, the names of the globals are refreshed before every prompt
+#[derive(Default)]
+pub struct LoxHelper {
+    pub globals: Vec<String>,
+}
+
+impl Completer for LoxHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = complete(line, pos, &self.globals);
+        let pairs = candidates
+            .into_iter()
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: name,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Validator for LoxHelper {}
+
+impl Helper for LoxHelper {}
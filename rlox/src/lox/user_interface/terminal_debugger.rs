@@ -0,0 +1,241 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::tree_walker::debugger::{Debugger, PauseReason, StepMode};
+use crate::tree_walker::interpreter::Interpreter;
+
+const HELP: &str = "\
+Commands:
+  c, continue       run until the next breakpoint
+  s, step           step into the next statement
+  n, next           step over function calls
+  o, out            run until the current function returns
+  b, break <line>   set a breakpoint, <module.lox>:<line> for a line of a module
+  d, delete <line>  remove a breakpoint
+  breakpoints       list the breakpoints
+  bt, stack         show the call stack
+  env               show the environment chain of the current frame
+  p, print <expr>   evaluate an expression in the current frame
+  l, list           show the source around the current line
+  q, quit           abort the script
+  h, help           show this help";
+
+// Debugger driven from a `(debug)` prompt, reads commands from `input` whenever the script pauses
+pub struct TerminalDebugger {
+    source_lines: Vec<String>,
+    // lines of the modules the script imported, read when the debugger first stops in one
+    module_lines: HashMap<PathBuf, Vec<String>>,
+    // a line of the script, or of the module whose path ends with the given file
+    breakpoints: BTreeSet<(Option<PathBuf>, usize)>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl TerminalDebugger {
+    pub fn new(source: &str, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        TerminalDebugger {
+            source_lines: source.lines().map(String::from).collect(),
+            module_lines: HashMap::new(),
+            breakpoints: BTreeSet::new(),
+            input,
+            output,
+        }
+    }
+
+    pub fn stdio(source: &str) -> Self {
+        Self::new(
+            source,
+            Box::new(io::BufReader::new(io::stdin())),
+            Box::new(io::stdout()),
+        )
+    }
+
+    // Source of the script, or of a module
+    fn lines_of(&mut self, module: Option<&Path>) -> &[String] {
+        let Some(module) = module else {
+            return &self.source_lines;
+        };
+        self.module_lines
+            .entry(module.to_path_buf())
+            .or_insert_with(|| {
+                fs::read_to_string(module)
+                    .map(|source| source.lines().map(String::from).collect())
+                    .unwrap_or_default()
+            })
+    }
+
+    fn source_line(&mut self, module: Option<&Path>, line: usize) -> String {
+        line.checked_sub(1)
+            .and_then(|index| self.lines_of(module).get(index))
+            .map_or(String::new(), |text| text.trim().to_string())
+    }
+
+    fn run_command(
+        &mut self,
+        interpreter: &mut Interpreter,
+        module: Option<&Path>,
+        line: usize,
+        command: &str,
+    ) -> io::Result<Option<StepMode>> {
+        let (name, argument) = match command.split_once(' ') {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "c" | "continue" => return Ok(Some(StepMode::Continue)),
+            "s" | "step" => return Ok(Some(StepMode::StepIn)),
+            "n" | "next" => return Ok(Some(StepMode::StepOver)),
+            "o" | "out" | "finish" => return Ok(Some(StepMode::StepOut)),
+            "q" | "quit" => return Ok(Some(StepMode::Terminate)),
+            "b" | "break" | "d" | "delete" => match parse_breakpoint(argument) {
+                Some(breakpoint) if name.starts_with('b') => {
+                    let location = location(breakpoint.0.as_deref(), breakpoint.1);
+                    self.breakpoints.insert(breakpoint);
+                    writeln!(self.output, "Breakpoint set at {}", location)?;
+                }
+                Some(breakpoint) => {
+                    let location = location(breakpoint.0.as_deref(), breakpoint.1);
+                    self.breakpoints.remove(&breakpoint);
+                    writeln!(self.output, "Breakpoint removed from {}", location)?;
+                }
+                None => writeln!(self.output, "Expect a line number: {} <line>", name)?,
+            },
+            "breakpoints" => {
+                for (module, line) in &self.breakpoints {
+                    writeln!(self.output, "{}", location(module.as_deref(), *line))?;
+                }
+            }
+            "bt" | "stack" => {
+                for (depth, frame) in interpreter.call_stack().iter().enumerate().rev() {
+                    writeln!(
+                        self.output,
+                        "#{} {} at {}",
+                        depth,
+                        frame.function,
+                        location(frame.module.as_deref(), frame.line)
+                    )?;
+                }
+            }
+            "env" => {
+                let top = interpreter.call_stack().len() - 1;
+                let mut environment = Some(interpreter.frame_environment(top));
+                let mut depth = 0;
+                while let Some(current) = environment {
+                    let scope = current.borrow();
+                    let label = if scope.parent_env.is_none() {
+                        "globals".to_string()
+                    } else {
+                        format!("scope {}", depth)
+                    };
+                    let bindings: Vec<String> = scope
+                        .bindings()
+                        .iter()
+                        .map(|(name, value)| format!("{} = {}", name, value.as_str()))
+                        .collect();
+                    writeln!(self.output, "{}: {}", label, bindings.join(", "))?;
+                    environment = scope.parent_env.clone();
+                    depth += 1;
+                }
+            }
+            "p" | "print" => {
+                let top = interpreter.call_stack().len() - 1;
+                match interpreter.evaluate_in_frame(argument, top) {
+                    Ok(value) => writeln!(self.output, "{}", value.as_str())?,
+                    Err(error) => writeln!(self.output, "Error: {:?}", error)?,
+                }
+            }
+            "l" | "list" => {
+                let first = line.saturating_sub(2).max(1);
+                let listing: Vec<(usize, String)> = (first..=line + 2)
+                    .zip(self.lines_of(module).iter().skip(first - 1).cloned())
+                    .collect();
+                for (number, text) in listing {
+                    let marker = if number == line { "->" } else { "  " };
+                    writeln!(self.output, "{} {:>4} {}", marker, number, text)?;
+                }
+            }
+            "h" | "help" => writeln!(self.output, "{}", HELP)?,
+            "" => (),
+            _ => writeln!(self.output, "Unknown command '{}', type 'help'", name)?,
+        }
+        Ok(None)
+    }
+}
+
+impl Debugger for TerminalDebugger {
+    fn is_breakpoint(&self, module: Option<&Path>, line: usize) -> bool {
+        self.breakpoints
+            .iter()
+            .any(|breakpoint| match (&breakpoint.0, module) {
+                (None, None) => breakpoint.1 == line,
+                (Some(file), Some(module)) => breakpoint.1 == line && module.ends_with(file),
+                _ => false,
+            })
+    }
+
+    fn paused(
+        &mut self,
+        interpreter: &mut Interpreter,
+        line: usize,
+        reason: PauseReason,
+    ) -> StepMode {
+        let function = interpreter
+            .call_stack()
+            .last()
+            .map_or("<script>".to_string(), |frame| frame.function.clone());
+        let module = interpreter
+            .call_stack()
+            .last()
+            .and_then(|frame| frame.module.clone());
+        let reason = match reason {
+            PauseReason::Entry => "entry",
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step => "step",
+        };
+        let source_line = self.source_line(module.as_deref(), line);
+        let _ = writeln!(
+            self.output,
+            "Paused ({}) at {} in {}: {}",
+            reason,
+            location(module.as_deref(), line),
+            function,
+            source_line
+        );
+
+        loop {
+            let _ = write!(self.output, "(debug) ");
+            let _ = self.output.flush();
+
+            let mut command = String::new();
+            match self.input.read_line(&mut command) {
+                // stdin was closed, there is nobody left to drive the session
+                Ok(0) | Err(_) => return StepMode::Terminate,
+                Ok(_) => (),
+            }
+            match self.run_command(interpreter, module.as_deref(), line, command.trim()) {
+                Ok(Some(mode)) => return mode,
+                Ok(None) => (),
+                Err(_) => return StepMode::Terminate,
+            }
+        }
+    }
+}
+
+// `12` is a line of the script, `util.lox:12` a line of a module
+fn parse_breakpoint(argument: &str) -> Option<(Option<PathBuf>, usize)> {
+    match argument.rsplit_once(':') {
+        Some((file, line)) => Some((Some(PathBuf::from(file)), line.parse().ok()?)),
+        None => Some((None, argument.parse().ok()?)),
+    }
+}
+
+// `line 12` in the script, `line 12 of util.lox` in a module
+fn location(module: Option<&Path>, line: usize) -> String {
+    match module.and_then(Path::file_name) {
+        Some(file) => format!("line {} of {}", line, file.to_string_lossy()),
+        None => format!("line {}", line),
+    }
+}
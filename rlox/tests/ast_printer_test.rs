@@ -0,0 +1,95 @@
+// tests/ast_printer_test.rs
+
+extern crate rulox;
+
+use rulox::tree_walker::ast_printer::{expr_to_sexpr, to_sexpr, to_tree};
+use rulox::tree_walker::parser::Parser;
+use rulox::user_interface::parse;
+
+fn sexpr(source: &str) -> String {
+    to_sexpr(&parse(source).unwrap())
+}
+
+#[test]
+fn test_sexpr_expressions() {
+    // GIVEN
+    let source = "-1 * (2 + 3) >= 4 or !a and f(b, \"c\");\nx = nil;";
+
+    // WHEN
+    let printed = sexpr(source);
+
+    // THEN
+    assert_eq!(
+        printed,
+        "(; (or (>= (* (- 1) (group (+ 2 3))) 4) (and (! a) (call f b \"c\"))))\n(; (= x nil))"
+    );
+}
+
+#[test]
+fn test_sexpr_statements() {
+    // GIVEN
+    let source = r#"
+var a;
+var b = true;
+fun f(x, y) { return x; }
+fun g() { return; }
+if (a) print 1; else { print 2; }
+if (b) print 3;
+for (var i = 0; i < 2; i = i + 1) print i;"#;
+
+    // WHEN
+    let printed = sexpr(source);
+
+    // THEN
+    let expected = [
+        "(var a)",
+        "(var b = true)",
+        "(fun f(x y) (return x))",
+        "(fun g() (return))",
+        "(if-else a (print 1) (block (print 2)))",
+        "(if b (print 3))",
+        "(block (var i = 0) (while (< i 2) (block (print i) (; (= i (+ i 1))))))",
+    ];
+    assert_eq!(printed, expected.join("\n"));
+}
+
+#[test]
+fn test_single_expression_sexpr() {
+    // GIVEN
+    let (ast, expression) = Parser::from_source("add(a, b) * -2")
+        .parse_expression()
+        .unwrap();
+
+    // WHEN
+    let printed = expr_to_sexpr(&ast, expression);
+
+    // THEN
+    assert_eq!(printed, "(* (call add a b) (- 2))");
+}
+
+#[test]
+fn test_tree() {
+    // GIVEN
+    let source = "fun add(a, b) { return a + b; }\nprint add(1, 2);";
+
+    // WHEN
+    let tree = to_tree(&parse(source).unwrap());
+
+    // THEN
+    assert_eq!(
+        tree,
+        "\
+Program
+├── Function add(a, b)
+│   └── Return
+│       └── Binary +
+│           ├── Variable a
+│           └── Variable b
+└── Print
+    └── Call
+        ├── Variable add
+        ├── Literal 1
+        └── Literal 2
+"
+    );
+}
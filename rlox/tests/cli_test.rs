@@ -0,0 +1,209 @@
+// tests/cli_test.rs
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn lox(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_inline_code_with_script_arguments() {
+    // GIVEN
+    let args = [
+        "-e",
+        "print argc(); print argv(1); print argv(2);",
+        "a",
+        "b",
+    ];
+
+    // WHEN
+    let output = lox(&args, "");
+
+    // THEN
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "2\nb\nnil\n");
+}
+
+#[test]
+fn test_print_writes_values_the_lox_way() {
+    // GIVEN
+    let source = r#"print "a" + "b"; print 7 ~/ 2; print 1.5; print 1 / 0; print true; print nil;"#;
+
+    // WHEN
+    let output = lox(&["-e", source], "");
+
+    // THEN
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "ab\n3\n1.5\nInfinity\ntrue\nnil\n");
+}
+
+#[test]
+fn test_runtime_errors_are_reported_with_their_line() {
+    // GIVEN
+    let undefined = "var a = 1;\nprint c;";
+    let division = "var zero = 0;\n\nprint 1 ~/ zero;";
+
+    // WHEN
+    let undefined = lox(&["-e", undefined], "");
+    let division = lox(&["-e", division], "");
+
+    // THEN
+    assert_eq!(undefined.status.code(), Some(70));
+    assert_eq!(
+        String::from_utf8_lossy(&undefined.stderr),
+        "[line 2] Error: undefined variable: c\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&division.stderr),
+        "[line 3] Error: Division by zero.\n"
+    );
+}
+
+#[test]
+fn test_script_from_stdin_and_file() {
+    // GIVEN
+    let path = std::env::temp_dir().join("cli_run.lox");
+    std::fs::write(&path, "print argv(0);").unwrap();
+    let path = path.to_string_lossy().to_string();
+
+    // WHEN
+    let from_stdin = lox(&["-"], "print 1 + 2;");
+    let from_file = lox(&["run", &path, "first"], "");
+
+    // THEN
+    assert_eq!(stdout(&from_stdin), "3\n");
+    assert_eq!(stdout(&from_file), "first\n");
+}
+
+#[test]
+fn test_dump_modes_and_version() {
+    // GIVEN
+    let tokens_args = ["--tokens", "-e", "var a;"];
+
+    // WHEN
+    let tokens = lox(&tokens_args, "");
+    let version = lox(&["--version"], "");
+    let ast_json = lox(&["--ast", "--json", "-e", "print 1;"], "");
+
+    // THEN
+    assert_eq!(
+        stdout(&tokens),
+        "1:0 Var 'var'\n1:4 Identifier 'a'\n1:5 Semicolon ';'\n1:6 Eof ''\n"
+    );
+    assert!(stdout(&ast_json).starts_with(r#"[{"expression":{"kind":"Literal""#));
+    assert_eq!(
+        stdout(&version),
+        format!("lox {}\n", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[test]
+fn test_exit_codes() {
+    // GIVEN
+    let cases: [(&[&str], i32); 6] = [
+        (&["--bogus"], 64),
+        (&["--json", "-e", "print 1;"], 64),
+        (&["--backend", "vm", "-e", "print 1;"], 64),
+        (&["-e", "print ;"], 65),
+        (&["/does/not/exist.lox"], 66),
+        (&["-e", "print -\"a\";"], 70),
+    ];
+
+    // WHEN
+    // THEN
+    for (args, code) in cases {
+        assert_eq!(lox(args, "").status.code(), Some(code), "{:?}", args);
+    }
+}
+
+#[test]
+fn test_coverage_exits_with_the_code_of_the_failing_script() {
+    // GIVEN
+    let path = std::env::temp_dir().join("cli_coverage_fails.lox");
+    std::fs::write(&path, "print 1;\nprint -\"a\";").unwrap();
+    let path = path.to_string_lossy().to_string();
+
+    // WHEN
+    let output = lox(&["--coverage", &path], "");
+
+    // THEN
+    assert_eq!(output.status.code(), Some(70));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("lines 2/2"));
+}
+
+#[test]
+fn test_coverage_reports_a_parse_error_and_measures_the_other_scripts() {
+    // GIVEN
+    let broken = std::env::temp_dir().join("cli_coverage_broken.lox");
+    std::fs::write(&broken, "print ;").unwrap();
+    let broken = broken.to_string_lossy().to_string();
+    let working = std::env::temp_dir().join("cli_coverage_works.lox");
+    std::fs::write(&working, "print 1;").unwrap();
+    let working = working.to_string_lossy().to_string();
+
+    // WHEN
+    let output = lox(&["--coverage", &broken, &working], "");
+
+    // THEN
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(stderr.starts_with(&format!("{}: [line 1", broken)), "{}", stderr);
+    assert!(stderr.contains(&working));
+    assert_eq!(stdout(&output), "1\n");
+}
+
+#[test]
+fn test_uncaught_exception_prints_stack_trace() {
+    // GIVEN
+    let source = "fun fail() {\n  throw \"boom\";\n}\nfail();";
+
+    // WHEN
+    let output = lox(&["-e", source], "");
+
+    // THEN
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Uncaught exception: boom\n    at fail (line 2)\n    at <script> (line 4)\n"
+    );
+}
+
+#[test]
+fn test_numeric_policy_option() {
+    // GIVEN
+    let source = "print 1 / 0;\nprint 0.0 / 0;";
+
+    // WHEN
+    let ieee = lox(&["-e", source], "");
+    let error = lox(&["--numeric", "error", "-e", source], "");
+    let integer = lox(&["-e", "print 1 ~/ 0;"], "");
+    let unknown = lox(&["--numeric", "strict", "-e", source], "");
+
+    // THEN
+    assert_eq!(ieee.status.code(), Some(0));
+    assert_eq!(stdout(&ieee), "Infinity\nNaN\n");
+    assert_eq!(error.status.code(), Some(70));
+    assert!(String::from_utf8_lossy(&error.stderr).contains("Division by zero."));
+    // integer division fails the same way without asking for it
+    assert_eq!(integer.status.code(), Some(70));
+    assert_eq!(integer.stderr, error.stderr);
+    assert_eq!(unknown.status.code(), Some(64));
+}
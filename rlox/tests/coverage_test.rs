@@ -0,0 +1,81 @@
+// tests/coverage_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::tree_walker::coverage::Coverage;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::parse;
+
+const SCRIPT: &str = r#"fun sign(n) {
+  if (n < 0) {
+    return -1;
+  } else {
+    return 1;
+  }
+}
+var i = 0;
+while (i < 2) {
+  i = i + 1;
+}
+print sign(i);"#;
+
+fn run_with_coverage(source: &str) -> Coverage {
+    let program = parse(source).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.enable_coverage(Coverage::new(&program.statements));
+    interpreter.interpret(program).unwrap();
+    interpreter.take_coverage().unwrap()
+}
+
+#[test]
+fn test_coverage_of_lines_and_branches() {
+    // GIVEN
+    let source = SCRIPT;
+
+    // WHEN
+    let coverage = run_with_coverage(source);
+
+    // THEN
+    assert_eq!(coverage.lines[&3], 0);
+    assert_eq!(coverage.lines[&5], 1);
+    assert_eq!(coverage.lines[&10], 2);
+    // the if only took the else branch, the loop ran twice and exited once
+    assert_eq!(coverage.branches[&2], [0, 1]);
+    assert_eq!(coverage.branches[&9], [2, 1]);
+    assert_eq!(coverage.lines_hit(), coverage.lines.len() - 1);
+    assert_eq!(coverage.branches_hit(), 3);
+}
+
+#[test]
+fn test_lcov_record() {
+    // GIVEN
+    let source = "fun never() {\n  if (true) print 1;\n}\nprint 2;";
+
+    // WHEN
+    let lcov = run_with_coverage(source).lcov("never.lox");
+
+    // THEN
+    assert_eq!(
+        lcov,
+        "TN:\nSF:never.lox\nBRDA:2,0,0,-\nBRDA:2,0,1,-\nBRF:2\nBRH:0\n\
+         DA:1,1\nDA:2,0\nDA:4,1\nLF:3\nLH:2\nend_of_record\n"
+    );
+}
+
+#[test]
+fn test_summary_lists_missed_lines() {
+    // GIVEN
+    let source = SCRIPT;
+
+    // WHEN
+    let summary = run_with_coverage(source).summary("sign.lox");
+
+    // THEN
+    assert_eq!(
+        summary,
+        "sign.lox: lines 7/8 (87.5%), branches 3/4 (75.0%)\n  not executed: 3"
+    );
+}
@@ -0,0 +1,229 @@
+// tests/debug_adapter_test.rs
+
+extern crate rulox;
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::rc::Rc;
+
+use rulox::debug_adapter::DebugAdapter;
+use rulox::language_server::transport::{read_message, write_message};
+use serde_json::{json, Value};
+
+// Writer that stays readable after the adapter consumed its boxed handle
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn request(seq: u64, command: &str, arguments: Value) -> Value {
+    json!({"seq": seq, "type": "request", "command": command, "arguments": arguments})
+}
+
+// Write the script to a temp file, play the requests in order and collect everything sent back
+fn run_session(name: &str, source: &str, requests: Vec<Value>) -> (String, Vec<Value>) {
+    let program = std::env::temp_dir().join(name);
+    fs::write(&program, source).unwrap();
+    let program = program.to_string_lossy().to_string();
+
+    let mut input = Vec::new();
+    for message in requests {
+        let mut message = message;
+        if message["command"] == "launch" {
+            message["arguments"]["program"] = json!(program);
+        }
+        if message["command"] == "setBreakpoints" && message["arguments"]["source"].is_null() {
+            message["arguments"]["source"] = json!({"path": program});
+        }
+        write_message(&mut input, &message).unwrap();
+    }
+
+    let output = SharedBuffer::default();
+    DebugAdapter::new(Box::new(Cursor::new(input)), Box::new(output.clone()))
+        .serve()
+        .unwrap();
+
+    let mut reader = Cursor::new(output.0.borrow().clone());
+    let mut messages = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        messages.push(message);
+    }
+    (program, messages)
+}
+
+fn response(messages: &[Value], seq: u64) -> &Value {
+    messages
+        .iter()
+        .find(|message| message["type"] == "response" && message["request_seq"] == json!(seq))
+        .expect("no response for request")
+}
+
+fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|message| message["event"] == json!(event))
+        .collect()
+}
+
+fn output(messages: &[Value]) -> String {
+    events(messages, "output")
+        .iter()
+        .filter(|event| event["body"]["category"] == "stdout")
+        .map(|event| event["body"]["output"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_breakpoint_stack_and_variables() {
+    // GIVEN
+    let source = "fun add(a, b) {\n  var sum = a + b;\n  return sum;\n}\nprint add(1, 2);\n";
+    let requests = vec![
+        request(1, "initialize", json!({"adapterID": "lox"})),
+        request(2, "launch", json!({})),
+        request(3, "setBreakpoints", json!({"breakpoints": [{"line": 3}]})),
+        request(4, "configurationDone", json!({})),
+        request(5, "stackTrace", json!({"threadId": 1})),
+        request(6, "scopes", json!({"frameId": 1})),
+        request(7, "variables", json!({"variablesReference": 1})),
+        request(
+            8,
+            "evaluate",
+            json!({"expression": "sum * 10", "frameId": 1}),
+        ),
+        request(9, "continue", json!({"threadId": 1})),
+        request(10, "disconnect", json!({})),
+    ];
+
+    // WHEN
+    let (program, messages) = run_session("dap_breakpoint.lox", source, requests);
+
+    // THEN
+    assert_eq!(events(&messages, "initialized").len(), 1);
+    assert_eq!(
+        response(&messages, 3)["body"]["breakpoints"][0]["verified"],
+        json!(true)
+    );
+
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 1);
+    assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+
+    let frames = &response(&messages, 5)["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "add");
+    assert_eq!(frames[0]["line"], json!(3));
+    assert_eq!(frames[0]["source"]["path"], json!(program));
+    assert_eq!(frames[1]["name"], "<script>");
+    assert_eq!(frames[1]["line"], json!(5));
+
+    assert_eq!(
+        response(&messages, 6)["body"]["scopes"][0]["name"],
+        "Locals"
+    );
+    let variables = &response(&messages, 7)["body"]["variables"];
+    assert_eq!(variables[0]["name"], "a");
+    assert_eq!(variables[0]["value"], "1");
+    assert_eq!(variables[2]["name"], "sum");
+    assert_eq!(variables[2]["value"], "3");
+    assert_eq!(response(&messages, 8)["body"]["result"], "30");
+
+    assert!(output(&messages).contains('3'));
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], json!(0));
+    assert_eq!(response(&messages, 10)["success"], json!(true));
+}
+
+#[test]
+fn test_stop_on_entry_and_step() {
+    // GIVEN
+    let source = "var a = 1;\nvar b = 2;\nprint a + b;\n";
+    let requests = vec![
+        request(1, "initialize", json!({})),
+        request(2, "launch", json!({"stopOnEntry": true})),
+        request(3, "configurationDone", json!({})),
+        request(4, "next", json!({"threadId": 1})),
+        request(5, "stackTrace", json!({"threadId": 1})),
+        request(6, "continue", json!({"threadId": 1})),
+        request(7, "disconnect", json!({})),
+    ];
+
+    // WHEN
+    let (_, messages) = run_session("dap_entry.lox", source, requests);
+
+    // THEN
+    let reasons: Vec<&Value> = events(&messages, "stopped")
+        .iter()
+        .map(|event| &event["body"]["reason"])
+        .collect();
+    assert_eq!(reasons, vec!["entry", "step"]);
+    assert_eq!(
+        response(&messages, 5)["body"]["stackFrames"][0]["line"],
+        json!(2)
+    );
+    assert!(events(&messages, "terminated").len() == 1);
+}
+
+#[test]
+fn test_disconnect_while_paused_terminates() {
+    // GIVEN
+    let source = "print 1;\nprint 2;\n";
+    let requests = vec![
+        request(1, "initialize", json!({})),
+        request(2, "launch", json!({"stopOnEntry": true})),
+        request(3, "configurationDone", json!({})),
+        request(4, "disconnect", json!({})),
+    ];
+
+    // WHEN
+    let (_, messages) = run_session("dap_disconnect.lox", source, requests);
+
+    // THEN
+    assert_eq!(output(&messages), "");
+    assert_eq!(events(&messages, "terminated").len(), 1);
+}
+
+#[test]
+fn test_output_is_sent_line_by_line_and_errors_as_reported() {
+    // GIVEN
+    let source = "print \"a\";\nprint 2;\nprint c;\n";
+    let requests = vec![
+        request(1, "initialize", json!({})),
+        request(2, "launch", json!({})),
+        request(3, "configurationDone", json!({})),
+        request(4, "disconnect", json!({})),
+    ];
+
+    // WHEN
+    let (_, messages) = run_session("dap_output.lox", source, requests);
+
+    // THEN
+    let output: Vec<(&str, &str)> = events(&messages, "output")
+        .iter()
+        .map(|event| {
+            (
+                event["body"]["category"].as_str().unwrap(),
+                event["body"]["output"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        output,
+        [
+            ("stdout", "a\n"),
+            ("stdout", "2\n"),
+            ("stderr", "[line 3] Error: undefined variable: c\n"),
+        ]
+    );
+    assert_eq!(
+        events(&messages, "exited")[0]["body"]["exitCode"],
+        json!(70)
+    );
+}
@@ -0,0 +1,228 @@
+// tests/debugger_test.rs
+
+extern crate rulox;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use rulox::tree_walker::debugger::{Debugger, PauseReason, StepMode};
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::{parse, run};
+
+const FIB: &str = r#"fun fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 2) + fib(n - 1);
+}
+var result = fib(3);
+print result;"#;
+
+#[derive(Debug, PartialEq)]
+struct Pause {
+    // file name of the module the line is in, `None` for the script
+    module: Option<String>,
+    line: usize,
+    reason: PauseReason,
+    stack: Vec<String>,
+    // value of the probe expression evaluated in the paused frame
+    probe: Option<String>,
+}
+
+// Replays a fixed list of resume commands and records every pause
+struct ScriptedDebugger {
+    // lines of the script, or `(Some("file.lox"), line)` for a module
+    breakpoints: Vec<(Option<&'static str>, usize)>,
+    commands: VecDeque<StepMode>,
+    probe: Option<&'static str>,
+    pauses: Rc<RefCell<Vec<Pause>>>,
+}
+
+impl Debugger for ScriptedDebugger {
+    fn is_breakpoint(&self, module: Option<&Path>, line: usize) -> bool {
+        let file = module.and_then(Path::file_name);
+        self.breakpoints.iter().any(|(name, at)| {
+            *at == line && file.map(|file| file.to_string_lossy()).as_deref() == *name
+        })
+    }
+
+    fn paused(
+        &mut self,
+        interpreter: &mut Interpreter,
+        line: usize,
+        reason: PauseReason,
+    ) -> StepMode {
+        let stack = interpreter
+            .call_stack()
+            .iter()
+            .map(|frame| format!("{}:{}", frame.function, frame.line))
+            .collect();
+        let top = interpreter.call_stack().len() - 1;
+        let probe = self
+            .probe
+            .map(|source| match interpreter.evaluate_in_frame(source, top) {
+                Ok(value) => value.as_str(),
+                Err(_) => "error".to_string(),
+            });
+        let module = interpreter
+            .call_stack()
+            .last()
+            .and_then(|frame| frame.module.as_deref())
+            .and_then(Path::file_name)
+            .map(|file| file.to_string_lossy().to_string());
+        self.pauses.borrow_mut().push(Pause {
+            module,
+            line,
+            reason,
+            stack,
+            probe,
+        });
+        self.commands.pop_front().unwrap_or(StepMode::Continue)
+    }
+}
+
+fn debug_session(
+    breakpoints: Vec<(Option<&'static str>, usize)>,
+    commands: Vec<StepMode>,
+    probe: Option<&'static str>,
+    stop_on_entry: bool,
+) -> (Vec<Pause>, String) {
+    let pauses = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.attach_debugger(
+        Box::new(ScriptedDebugger {
+            breakpoints,
+            commands: commands.into(),
+            probe,
+            pauses: Rc::clone(&pauses),
+        }),
+        stop_on_entry,
+    );
+
+    let _ = run(FIB, &mut interpreter);
+    let output = String::from_utf8_lossy(&interpreter.get_outpout()).to_string();
+    let pauses = pauses.take();
+    (pauses, output)
+}
+
+#[test]
+fn breakpoint_pauses_every_visit_with_the_call_stack() {
+    // GIVEN
+    let breakpoints = vec![(None, 2)];
+
+    // WHEN
+    let (pauses, output) = debug_session(breakpoints, vec![], Some("n"), false);
+
+    // THEN
+    let visits: Vec<(usize, Option<String>)> = pauses
+        .iter()
+        .map(|pause| (pause.stack.len(), pause.probe.clone()))
+        .collect();
+    // fib(3) -> fib(1), fib(2) -> fib(0), fib(1)
+    assert_eq!(
+        visits,
+        vec![
+            (2, Some("3".to_string())),
+            (3, Some("1".to_string())),
+            (3, Some("2".to_string())),
+            (4, Some("0".to_string())),
+            (4, Some("1".to_string())),
+        ]
+    );
+    assert_eq!(pauses[1].stack, vec!["<script>:5", "fib:3", "fib:2"]);
+    assert!(pauses
+        .iter()
+        .all(|pause| pause.reason == PauseReason::Breakpoint));
+    assert_eq!(output, "2");
+}
+
+#[test]
+fn step_over_stays_in_frame_and_step_in_enters_calls() {
+    // GIVEN
+    let commands = vec![StepMode::StepOver, StepMode::StepIn, StepMode::StepOver];
+
+    // WHEN
+    let (pauses, _) = debug_session(vec![], commands, None, true);
+
+    // THEN
+    let lines: Vec<(usize, PauseReason)> = pauses
+        .iter()
+        .map(|pause| (pause.line, pause.reason))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (1, PauseReason::Entry),
+            (5, PauseReason::Step),
+            (2, PauseReason::Step),
+            (3, PauseReason::Step),
+        ]
+    );
+}
+
+#[test]
+fn step_out_returns_to_the_caller_and_terminate_aborts() {
+    // GIVEN
+    let breakpoints = vec![(None, 5)];
+    let commands = vec![StepMode::StepIn, StepMode::StepOut, StepMode::Terminate];
+
+    // WHEN
+    let (pauses, output) = debug_session(breakpoints, commands, Some("result"), false);
+
+    // THEN
+    assert_eq!(pauses.len(), 3);
+    assert_eq!(pauses[1].stack, vec!["<script>:5", "fib:2"]);
+    assert_eq!(pauses[2].line, 6);
+    assert_eq!(pauses[2].stack, vec!["<script>:6"]);
+    assert_eq!(pauses[2].probe, Some("2".to_string()));
+    assert_eq!(output, "");
+}
+
+#[test]
+fn breakpoints_and_steps_reach_the_code_of_modules() {
+    // GIVEN
+    let dir = std::env::temp_dir().join("lox_debugger_modules");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("util.lox"),
+        "var base = 10;\nfun add(n) {\n  return base + n;\n}",
+    )
+    .unwrap();
+    let main = dir.join("main.lox");
+    fs::write(&main, "import \"util.lox\";\nprint add(1);").unwrap();
+    let pauses = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.set_script_path(&main);
+    interpreter.attach_debugger(
+        Box::new(ScriptedDebugger {
+            breakpoints: vec![(Some("util.lox"), 1), (Some("util.lox"), 3)],
+            commands: vec![StepMode::Continue, StepMode::Continue].into(),
+            probe: Some("base"),
+            pauses: Rc::clone(&pauses),
+        }),
+        false,
+    );
+
+    // WHEN
+    let statements = parse(&fs::read_to_string(&main).unwrap()).unwrap();
+    interpreter.interpret(statements).unwrap();
+
+    // THEN
+    let pauses = pauses.take();
+    let visits: Vec<(Option<&str>, usize, Option<&str>)> = pauses
+        .iter()
+        .map(|pause| (pause.module.as_deref(), pause.line, pause.probe.as_deref()))
+        .collect();
+    assert_eq!(
+        visits,
+        vec![
+            (Some("util.lox"), 1, Some("error")),
+            (Some("util.lox"), 3, Some("10")),
+        ]
+    );
+    assert_eq!(pauses[1].stack, vec!["<script>:2", "add:3"]);
+}
@@ -0,0 +1,166 @@
+// tests/exceptions_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::run;
+use rulox::LoxError;
+
+fn run_source(source: &str) -> (String, Result<(), LoxError>) {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    let result = run(source, &mut interpreter);
+    let output = String::from_utf8(interpreter.get_outpout()).unwrap();
+    (output, result)
+}
+
+#[test]
+fn test_thrown_value_is_caught_and_finally_runs() {
+    // GIVEN
+    let source = r#"try {
+  print "a";
+  throw 42;
+  print "not reached";
+} catch (e) {
+  print e;
+} finally {
+  print "b";
+}"#;
+
+    // WHEN
+    let (output, result) = run_source(source);
+
+    // THEN
+    assert!(result.is_ok());
+    assert_eq!(output, "a42b");
+}
+
+#[test]
+fn test_runtime_errors_are_caught_as_error_values() {
+    // GIVEN
+    let source = r#"fun add(a, b) { return a + b; }
+try {
+  print missing;
+} catch (e) {
+  print errorMessage(e);
+  print errorLine(e);
+}
+try {
+  add(1);
+} catch (e) {
+  print errorMessage(e);
+}
+try {
+  print -"a";
+} catch (e) {
+  print errorLine(e);
+}
+try { throw "plain"; } catch (e) { print errorLine(e); }"#;
+
+    // WHEN
+    let (output, result) = run_source(source);
+
+    // THEN
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(
+        output,
+        "undefined variable: missing3Expect 2 arguments, but got 1 arguments.14nil"
+    );
+}
+
+#[test]
+fn test_uncaught_exception_carries_the_stack() {
+    // GIVEN
+    let source = r#"fun inner() {
+  throw "deep";
+}
+fun outer() {
+  inner();
+}
+outer();"#;
+
+    // WHEN
+    let (_, result) = run_source(source);
+
+    // THEN
+    let Err(LoxError::Throw(exception)) = result else {
+        panic!("expected an exception, got {:?}", result);
+    };
+    assert_eq!(
+        exception.stack,
+        vec![
+            ("inner".to_string(), 2),
+            ("outer".to_string(), 5),
+            ("<script>".to_string(), 7)
+        ]
+    );
+    assert_eq!(
+        exception.stack_trace(),
+        "Uncaught exception: deep\n    at inner (line 2)\n    at outer (line 5)\n    at <script> (line 7)"
+    );
+}
+
+#[test]
+fn test_finally_runs_on_return_and_rethrow() {
+    // GIVEN
+    let source = r#"fun early() {
+  try {
+    return "returned";
+  } finally {
+    print "cleanup";
+  }
+}
+print early();
+fun rethrow() {
+  try {
+    throw "first";
+  } catch (e) {
+    throw e + " again";
+  } finally {
+    print "still";
+  }
+}
+try { rethrow(); } catch (e) { print e; }"#;
+
+    // WHEN
+    let (output, result) = run_source(source);
+
+    // THEN
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(output, "cleanupreturnedstillfirst again");
+}
+
+#[test]
+fn test_scopes_are_restored_after_a_catch() {
+    // GIVEN
+    let source = r#"var a = "global";
+fun fail() { var a = "local"; throw a; }
+try {
+  var a = "try";
+  fail();
+} catch (e) {
+  print e;
+}
+print a;"#;
+
+    // WHEN
+    let (output, result) = run_source(source);
+
+    // THEN
+    assert!(result.is_ok());
+    assert_eq!(output, "localglobal");
+}
+
+#[test]
+fn test_try_needs_catch_or_finally() {
+    // GIVEN
+    let source = "try { print 1; }";
+
+    // WHEN
+    let (_, result) = run_source(source);
+
+    // THEN
+    assert!(matches!(result, Err(LoxError::ParserError(_))));
+}
@@ -2,6 +2,8 @@
 
 extern crate rulox;
 
+use std::io;
+
 use rulox::tree_walker::interpreter::Interpreter;
 use rulox::user_interface::run;
 
@@ -17,7 +19,6 @@ fn remove_whitespace(input: &str) -> String {
 fn convert_to_string(output: Vec<u8>) -> String {
     String::from_utf8_lossy(&output)
         .lines()
-        .map(|line| line)
         .collect::<Vec<&str>>()
         .join(" ")
 }
@@ -27,6 +28,7 @@ fn convert_to_string(output: Vec<u8>) -> String {
 fn test_scope() {
     // SETUP
     let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
     // GIVEN
     let input = String::from(
         r#"var a = "global a";
@@ -80,12 +82,14 @@ print c;"#,
 fn test_grouping() {
     //given
     let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
     let input = String::from(
         r#"
         var a = ((1 + 3) * (6-3))/2;
 print a;"#,
     );
-    let expected = r#" 6
+    // `/` divides as floats even when both operands are integers
+    let expected = r#" 6.0
     "#;
     let processed_expected = remove_whitespace(expected);
 
@@ -108,6 +112,7 @@ print a;"#,
 fn function_declaration() {
     //given
     let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
     let input = String::from(
         r#"
         fun sayhi (first,last) { print "Hi, " + first + "" + last + "!"; }
@@ -136,8 +141,9 @@ fn function_declaration() {
 fn return_statement() {
     //given
     let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
     let input = String::from(r#"fun foo() {return 1;} print foo(); "#);
-    let expected = r#"Hello "#;
+    let expected = r#"1 "#;
     let processed_expected = remove_whitespace(expected);
 
     //WHEN
@@ -158,6 +164,7 @@ fn return_statement() {
 fn return_statement_v2() {
     //given
     let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
     let input = String::from(r#" fun fib(n) {
      if (n <= 1) return n;
     return fib(n - 2) + fib(n - 1);
@@ -165,7 +172,7 @@ fn return_statement_v2() {
 for (var i = 2; i < 4; i = i + 1) {
   print fib(i);
 }  "#);
-    let expected = r#"Hello "#;
+    let expected = r#"12 "#;
     let processed_expected = remove_whitespace(expected);
 
     //WHEN
@@ -186,8 +193,9 @@ for (var i = 2; i < 4; i = i + 1) {
 fn smaller_or_equals() {
     //given
     let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
     let input = String::from(r#" var a =1; if (a <=1) print a; else print "hello";  "#);
-    let expected = r#"Hello "#;
+    let expected = r#"1 "#;
     let processed_expected = remove_whitespace(expected);
 
     //WHEN
@@ -202,4 +210,21 @@ fn smaller_or_equals() {
     let output_str = convert_to_string(output);
 
     assert_eq!(output_str, processed_expected.trim());
-}
\ No newline at end of file
+}
+
+#[test]
+fn error_lines_count_from_one() {
+    //given
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    let input = String::from("print 1;\nprint ;");
+
+    //WHEN
+    let result = run(&input, &mut interpreter);
+
+    //THEN
+    match result {
+        Err(rulox::LoxError::ParserError(e)) => assert_eq!(e.line(), 2),
+        other => panic!("expected a parser error, got {:?}", other),
+    }
+}
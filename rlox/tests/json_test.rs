@@ -0,0 +1,107 @@
+// tests/json_test.rs
+
+extern crate rulox;
+
+use rulox::user_interface::{dump_ast_json, dump_tokens_json};
+use serde_json::json;
+
+#[test]
+fn test_tokens_to_json() {
+    // GIVEN
+    let source = "var s = \"hi\";";
+
+    // WHEN
+    let tokens = dump_tokens_json(source).unwrap();
+
+    // THEN
+    assert_eq!(
+        tokens[0],
+        json!({
+            "type": "Var",
+            "lexeme": "var",
+            "literal": null,
+            "span": {"start": {"line": 1, "column": 0}, "end": {"line": 1, "column": 3}},
+        })
+    );
+    assert_eq!(tokens[3]["type"], "String");
+    assert_eq!(tokens[3]["literal"], "hi");
+    assert_eq!(tokens[3]["span"]["end"]["column"], 12);
+    assert_eq!(tokens.as_array().unwrap().last().unwrap()["type"], "Eof");
+}
+
+#[test]
+fn test_ast_to_json() {
+    // GIVEN
+    let source = "fun add(a, b) {\n  return a + b;\n}\nprint add(1, 2);";
+
+    // WHEN
+    let ast = dump_ast_json(source).unwrap();
+
+    // THEN
+    let function = &ast[0];
+    assert_eq!(function["kind"], "Function");
+    assert_eq!(function["parameters"], json!(["a", "b"]));
+    assert_eq!(function["body"][0]["kind"], "Return");
+    assert_eq!(function["body"][0]["line"], 2);
+    assert_eq!(
+        function["body"][0]["value"],
+        json!({
+            "kind": "Binary",
+            "operator": "+",
+            "left": {
+                "kind": "Variable",
+                "name": "a",
+                "span": {"start": {"line": 2, "column": 9}, "end": {"line": 2, "column": 10}},
+            },
+            "right": {
+                "kind": "Variable",
+                "name": "b",
+                "span": {"start": {"line": 2, "column": 13}, "end": {"line": 2, "column": 14}},
+            },
+            "span": {"start": {"line": 2, "column": 9}, "end": {"line": 2, "column": 14}},
+        })
+    );
+
+    let call = &ast[1]["expression"];
+    assert_eq!(call["kind"], "Call");
+    assert_eq!(call["arguments"][1]["value"], json!(2));
+    assert_eq!(
+        call["span"],
+        json!({"start": {"line": 4, "column": 6}, "end": {"line": 4, "column": 15}})
+    );
+}
+
+#[test]
+fn test_span_of_a_string_over_several_lines() {
+    // GIVEN
+    let source = "var s = \"one\ntwo\nthree\";\nprint \"${s}\n!\";";
+
+    // WHEN
+    let tokens = dump_tokens_json(source).unwrap();
+
+    // THEN
+    assert_eq!(
+        tokens[3]["span"],
+        json!({"start": {"line": 1, "column": 8}, "end": {"line": 3, "column": 6}})
+    );
+    assert_eq!(
+        tokens[6]["span"],
+        json!({"start": {"line": 4, "column": 6}, "end": {"line": 4, "column": 9}})
+    );
+    assert_eq!(
+        tokens[8]["span"],
+        json!({"start": {"line": 4, "column": 10}, "end": {"line": 5, "column": 2}})
+    );
+}
+
+#[test]
+fn test_json_dump_reports_parse_errors() {
+    // GIVEN
+    let source = "print ;";
+
+    // WHEN
+    let result = dump_ast_json(source);
+
+    // THEN
+    assert!(result.is_err());
+}
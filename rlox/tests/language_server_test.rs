@@ -0,0 +1,187 @@
+// tests/language_server_test.rs
+
+extern crate rulox;
+
+use std::io::Cursor;
+
+use rulox::language_server::transport::{read_message, write_message};
+use rulox::language_server::LanguageServer;
+use serde_json::{json, Value};
+
+const URI: &str = "file:///test.lox";
+
+// Frame the messages like an editor would and collect everything the server writes back
+fn run_session(messages: Vec<Value>) -> (i32, Vec<Value>) {
+    let mut input = Vec::new();
+    for message in &messages {
+        write_message(&mut input, message).unwrap();
+    }
+
+    let mut output = Vec::new();
+    let exit_code = LanguageServer::default()
+        .serve(&mut Cursor::new(input), &mut output)
+        .unwrap();
+
+    let mut reader = Cursor::new(output);
+    let mut responses = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        responses.push(message);
+    }
+    (exit_code, responses)
+}
+
+fn open_document(text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": URI, "languageId": "lox", "version": 1, "text": text}},
+    })
+}
+
+fn position_request(id: u64, method: &str, line: u64, character: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": {
+            "textDocument": {"uri": URI},
+            "position": {"line": line, "character": character},
+            "context": {"includeDeclaration": true},
+        },
+    })
+}
+
+fn response(responses: &[Value], id: u64) -> &Value {
+    &responses
+        .iter()
+        .find(|message| message["id"] == json!(id))
+        .expect("no response for request")["result"]
+}
+
+#[test]
+fn publishes_parser_diagnostics() {
+    // GIVEN
+    let source = "var a = 1;\nvar = 2;\nprint a;";
+
+    // WHEN
+    let (_, responses) = run_session(vec![open_document(source)]);
+
+    // THEN
+    let params = &responses[0]["params"];
+    assert_eq!(responses[0]["method"], "textDocument/publishDiagnostics");
+    assert_eq!(params["uri"], URI);
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+    assert_eq!(diagnostics[0]["message"], "at '=': expect variable name.");
+}
+
+#[test]
+fn definition_and_references_follow_scopes() {
+    // GIVEN
+    let source = r#"var n = 10;
+fun fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 2) + fib(n - 1);
+}
+print fib(n);"#;
+
+    // WHEN
+    let (_, responses) = run_session(vec![
+        open_document(source),
+        // `fib` in `print fib(n);`
+        position_request(1, "textDocument/definition", 5, 7),
+        // `n` inside the body of fib refers to the parameter
+        position_request(2, "textDocument/references", 2, 6),
+        // global `n` in `print fib(n);`
+        position_request(3, "textDocument/references", 5, 10),
+    ]);
+
+    // THEN
+    assert_eq!(
+        response(&responses, 1)["range"],
+        json!({"start": {"line": 1, "character": 4}, "end": {"line": 1, "character": 7}})
+    );
+    let parameter_lines: Vec<&Value> = response(&responses, 2)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| &location["range"]["start"]["line"])
+        .collect();
+    assert_eq!(parameter_lines, vec![1, 2, 2, 3, 3]);
+    assert_eq!(response(&responses, 3).as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn hover_shows_function_arity() {
+    // GIVEN
+    let source = "fun add(a, b) { return a + b; }\nadd(1, 2);";
+
+    // WHEN
+    let (_, responses) = run_session(vec![
+        open_document(source),
+        position_request(1, "textDocument/hover", 1, 1),
+    ]);
+
+    // THEN
+    assert_eq!(
+        response(&responses, 1)["contents"]["value"],
+        "```lox\nfun add(a, b)\n```\narity: 2"
+    );
+}
+
+#[test]
+fn document_symbols_list_top_level_functions() {
+    // GIVEN
+    let source = "fun outer() { fun inner() {} }\nvar x = 1;\nfun other(a) {}";
+
+    // WHEN
+    let (exit_code, responses) = run_session(vec![
+        open_document(source),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "textDocument/documentSymbol",
+            "params": {"textDocument": {"uri": URI}},
+        }),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ]);
+
+    // THEN
+    let names: Vec<&Value> = response(&responses, 1)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| &symbol["name"])
+        .collect();
+    assert_eq!(names, vec!["outer", "other"]);
+    assert_eq!(exit_code, 0);
+}
+
+#[test]
+fn malformed_message_gets_a_parse_error_and_the_server_keeps_running() {
+    // GIVEN
+    let mut input = b"Content-Length: 9\r\n\r\n{not json".to_vec();
+    write_message(
+        &mut input,
+        &json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"}),
+    )
+    .unwrap();
+    write_message(&mut input, &json!({"jsonrpc": "2.0", "method": "exit"})).unwrap();
+
+    // WHEN
+    let mut output = Vec::new();
+    let exit_code = LanguageServer::default()
+        .serve(&mut Cursor::new(input), &mut output)
+        .unwrap();
+    let mut reader = Cursor::new(output);
+    let parse_error = read_message(&mut reader).unwrap().unwrap();
+    let shutdown = read_message(&mut reader).unwrap().unwrap();
+
+    // THEN
+    assert_eq!(parse_error["error"]["code"], json!(-32700));
+    assert_eq!(parse_error["id"], Value::Null);
+    assert_eq!(shutdown["id"], json!(1));
+    assert_eq!(exit_code, 0);
+}
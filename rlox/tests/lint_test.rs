@@ -0,0 +1,88 @@
+// tests/lint_test.rs
+
+extern crate rulox;
+
+use rulox::lint::{lint, Lint, LintConfig};
+use rulox::user_interface::parse;
+
+fn lint_source(source: &str, config: &LintConfig) -> Vec<(Lint, usize)> {
+    let statements = parse(source).unwrap();
+    lint(&statements, config)
+        .into_iter()
+        .map(|warning| (warning.lint, warning.line))
+        .collect()
+}
+
+#[test]
+fn reports_every_lint() {
+    // GIVEN
+    let input = r#"var n = 10;
+fun fib(n, n) {
+  var unused = 1;
+  if (n <= 1) return n;
+  return fib(n - 2, 1);
+  print "never";
+}
+fun outer() {
+  var a = a;
+  print fib;
+  return a;
+}
+outer(1);"#;
+
+    // WHEN
+    let warnings = lint_source(input, &LintConfig::default());
+
+    // THEN
+    assert_eq!(
+        warnings,
+        vec![
+            (Lint::Shadowing, 2),
+            (Lint::DuplicateParameter, 2),
+            (Lint::UnusedVariable, 3),
+            (Lint::UnreachableCode, 6),
+            (Lint::SelfInitializer, 9),
+            (Lint::PrintFunction, 10),
+            (Lint::ArityMismatch, 13),
+        ]
+    );
+}
+
+#[test]
+fn clean_script_has_no_warnings() {
+    // GIVEN
+    let input = r#"fun fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 2) + fib(n - 1);
+}
+for (var i = 0; i < 4; i = i + 1) {
+  print fib(i);
+}"#;
+
+    // WHEN
+    let warnings = lint_source(input, &LintConfig::default());
+
+    // THEN
+    assert_eq!(warnings, vec![]);
+}
+
+#[test]
+fn disabled_lints_are_not_reported() {
+    // GIVEN
+    let input = r#"fun count(n) {
+  var unused;
+  if (n > 1) return 1; else return 2;
+  print n;
+}
+var count = 3;
+count(1, 2);"#;
+    let mut config = LintConfig::default();
+    config.disable(Lint::UnusedVariable);
+
+    // WHEN
+    let warnings = lint_source(input, &config);
+
+    // THEN
+    // `count` was redeclared as a variable, so its arity is no longer known
+    assert_eq!(warnings, vec![(Lint::UnreachableCode, 4)]);
+}
@@ -0,0 +1,237 @@
+// tests/modules_test.rs
+
+extern crate rulox;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rulox::tree_walker::coverage::Coverage;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::parse;
+use rulox::LoxError;
+
+// writes the files into a directory of their own, returns the path of its `main.lox`
+fn write_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lox_modules_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    for (file, source) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    dir.join("main.lox")
+}
+
+fn run_main(main: &Path, interpreter: &mut Interpreter) -> Result<(), LoxError> {
+    let statements = parse(&fs::read_to_string(main).unwrap()).unwrap();
+    interpreter.set_script_path(main);
+    interpreter.interpret(statements)
+}
+
+// runs `main.lox` of the project
+fn run_project(name: &str, files: &[(&str, &str)]) -> (Interpreter, Result<(), LoxError>) {
+    let main = write_project(name, files);
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    let result = run_main(&main, &mut interpreter);
+    (interpreter, result)
+}
+
+fn output(interpreter: &Interpreter) -> String {
+    String::from_utf8(interpreter.get_outpout()).unwrap()
+}
+
+fn error_message(result: Result<(), LoxError>) -> String {
+    match result {
+        Err(LoxError::Runtime(e)) => e.message().to_string(),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_import_binds_every_public_global() {
+    // GIVEN
+    let files = [
+        (
+            "main.lox",
+            "import \"lib/math.lox\";\nprint square(3);\nprint _helper;",
+        ),
+        (
+            "lib/math.lox",
+            "var _helper = 1;\nfun square(n) { return n * n; }",
+        ),
+    ];
+
+    // WHEN
+    let (interpreter, result) = run_project("all", &files);
+
+    // THEN
+    assert_eq!(output(&interpreter), "9");
+    assert_eq!(error_message(result), "undefined variable: _helper");
+}
+
+#[test]
+fn test_named_import_relative_to_the_importing_module() {
+    // GIVEN
+    let files = [
+        (
+            "main.lox",
+            "import { greet } from \"lib/greet.lox\";\nprint greet();\nprint name;",
+        ),
+        (
+            "lib/greet.lox",
+            "import \"name.lox\";\nfun greet() { return \"hi \" + name; }",
+        ),
+        ("lib/name.lox", "var name = \"lox\";"),
+    ];
+
+    // WHEN
+    let (interpreter, result) = run_project("named", &files);
+
+    // THEN
+    assert_eq!(output(&interpreter), "hi lox");
+    assert_eq!(error_message(result), "undefined variable: name");
+}
+
+#[test]
+fn test_module_runs_once() {
+    // GIVEN
+    let files = [
+        (
+            "main.lox",
+            "import \"counter.lox\";\nimport { count } from \"counter.lox\";\nprint count;",
+        ),
+        ("counter.lox", "print \"loading\";\nvar count = 1;"),
+    ];
+
+    // WHEN
+    let (interpreter, result) = run_project("once", &files);
+
+    // THEN
+    assert!(result.is_ok());
+    assert_eq!(output(&interpreter), "loading1");
+}
+
+#[test]
+fn test_module_functions_use_their_own_globals() {
+    // GIVEN
+    let files = [
+        (
+            "main.lox",
+            "var total = 100;\nimport \"acc.lox\";\nadd(2);\nadd(3);\nprint total;\nprint sum();",
+        ),
+        (
+            "acc.lox",
+            "var total = 0;\nfun add(n) { total = total + n; }\nfun sum() { return total; }",
+        ),
+    ];
+
+    // WHEN
+    let (interpreter, result) = run_project("globals", &files);
+
+    // THEN
+    assert!(result.is_ok());
+    // the import copied the module's `total`, the functions keep updating the module's own one
+    assert_eq!(output(&interpreter), "05");
+}
+
+#[test]
+fn test_import_cycle_is_an_error() {
+    // GIVEN
+    let files = [
+        ("main.lox", "import \"a.lox\";"),
+        ("a.lox", "import \"b.lox\";"),
+        ("b.lox", "import \"a.lox\";"),
+    ];
+
+    // WHEN
+    let (_, result) = run_project("cycle", &files);
+
+    // THEN
+    let message = error_message(result);
+    assert!(message.starts_with("Import cycle: "), "{}", message);
+    assert!(message.contains("b.lox -> "), "{}", message);
+    assert!(message.ends_with("a.lox"), "{}", message);
+    assert_eq!(message.matches("a.lox").count(), 2);
+}
+
+#[test]
+fn test_missing_export_and_missing_file() {
+    // GIVEN
+    let files = [
+        ("main.lox", "import { nope } from \"lib.lox\";"),
+        ("lib.lox", "var yes = true;"),
+    ];
+    let missing = [("main.lox", "import \"missing.lox\";")];
+
+    // WHEN
+    let (_, export_result) = run_project("export", &files);
+    let (_, file_result) = run_project("missing", &missing);
+
+    // THEN
+    assert_eq!(
+        error_message(export_result),
+        "Module \"lib.lox\" doesn't export 'nope'."
+    );
+    assert!(error_message(file_result).starts_with("Can't import \"missing.lox\": "));
+}
+
+#[test]
+fn test_module_lines_are_profiled_and_covered() {
+    // GIVEN
+    let main = write_project(
+        "tracked",
+        &[
+            ("main.lox", "import \"lib/util.lox\";\nprint twice(2);"),
+            (
+                "lib/util.lox",
+                "fun twice(n) {\n  if (n > 0) {\n    return n * 2;\n  }\n  return 0;\n}",
+            ),
+        ],
+    );
+    let source = fs::read_to_string(&main).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.enable_profiler();
+    interpreter.enable_coverage(Coverage::new(&parse(&source).unwrap().statements));
+
+    // WHEN
+    let result = run_main(&main, &mut interpreter);
+
+    // THEN
+    assert!(result.is_ok());
+    let util = main.with_file_name("lib/util.lox").canonicalize().unwrap();
+    let coverage = interpreter.take_coverage().unwrap();
+    assert_eq!(coverage.lines.get(&1), Some(&1));
+    assert_eq!(coverage.lines.get(&3), None);
+    let module = &coverage.modules[&util];
+    assert_eq!(module.lines.get(&3), Some(&1));
+    assert_eq!(module.lines.get(&5), Some(&0));
+    assert_eq!(module.branches[&2], [1, 0]);
+    let profile = interpreter.take_profile().unwrap();
+    assert_eq!(profile.module_lines[&util].get(&3), Some(&1));
+    assert!(profile.report().contains("util.lox:3"));
+}
+
+#[test]
+fn test_modules_can_read_the_script_arguments() {
+    // GIVEN
+    let main = write_project(
+        "args",
+        &[
+            ("main.lox", "import \"args.lox\";\nprint first();"),
+            ("args.lox", "print argc();\nfun first() { return argv(0); }"),
+        ],
+    );
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.set_args(vec!["a".to_string(), "b".to_string()]);
+
+    // WHEN
+    let result = run_main(&main, &mut interpreter);
+
+    // THEN
+    assert!(result.is_ok());
+    assert_eq!(output(&interpreter), "2a");
+}
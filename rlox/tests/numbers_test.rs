@@ -0,0 +1,176 @@
+// tests/numbers_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::frontend::lox_value::LoxValue;
+use rulox::tree_walker::arithmetic::NumericPolicy;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::tree_walker::optimizer::OptLevel;
+use rulox::user_interface::run;
+use rulox::LoxError;
+
+fn evaluate(source: &str) -> Result<LoxValue, LoxError> {
+    Interpreter::new().evaluate_in_frame(source, 0)
+}
+
+fn evaluate_strict(source: &str) -> Result<LoxValue, LoxError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.set_numeric_policy(NumericPolicy::Error);
+    interpreter.evaluate_in_frame(source, 0)
+}
+
+#[test]
+fn test_literals_and_formatting() {
+    // GIVEN
+    let sources = ["7", "7.0", "2.5", "-3", "9007199254740993"];
+
+    // WHEN
+    let values: Vec<LoxValue> = sources.iter().map(|s| evaluate(s).unwrap()).collect();
+
+    // THEN
+    assert_eq!(values[0], LoxValue::Integer(7));
+    assert!(matches!(values[1], LoxValue::Float(_)));
+    let printed: Vec<String> = values.iter().map(LoxValue::as_str).collect();
+    // integers keep every digit, past 2^53 a float couldn't
+    assert_eq!(printed, ["7", "7.0", "2.5", "-3", "9007199254740993"]);
+}
+
+#[test]
+fn test_promotion_and_division() {
+    // GIVEN
+    let cases = [
+        ("10 / 4", "2.5"),
+        ("10 / 5", "2.0"),
+        ("10 ~/ 3", "3"),
+        ("-7 ~/ 2", "-4"),
+        ("7.5 ~/ 2", "3.0"),
+        ("-7 % 3", "2"),
+        ("7 % -3", "-2"),
+        ("7.5 % 2", "1.5"),
+        ("1 + 0.5", "1.5"),
+        ("2 * 3", "6"),
+        ("1 == 1.0", "true"),
+        ("2 < 2.5", "true"),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        assert_eq!(evaluate(source).unwrap().as_str(), expected, "{}", source);
+    }
+}
+
+#[test]
+fn test_integer_overflow_is_an_error() {
+    // GIVEN
+    let sources = [
+        "9223372036854775807 + 1",
+        "-9223372036854775807 - 2",
+        "4611686018427387904 * 2",
+    ];
+
+    // WHEN
+    // THEN
+    for source in sources {
+        assert!(
+            matches!(evaluate(source), Err(LoxError::Interpreter(_))),
+            "{}",
+            source
+        );
+    }
+    assert!(matches!(
+        evaluate("99999999999999999999"),
+        Err(LoxError::ScannerError(_))
+    ));
+}
+
+#[test]
+fn test_ieee_policy_prints_infinity_and_nan_like_lox() {
+    // GIVEN
+    let cases = [
+        ("1 / 0", "Infinity"),
+        ("-1 / 0.0", "-Infinity"),
+        ("0 / 0", "NaN"),
+        ("1.5 % 0", "NaN"),
+        ("(-8.0) ** 0.5", "NaN"),
+        ("0 / 0 == 0 / 0", "false"),
+        ("0 / 0 != 0 / 0", "true"),
+        ("\"${1 / 0}\"", "Infinity"),
+        ("sqrt(16)", "4.0"),
+        ("sqrt(-1)", "NaN"),
+        ("log(0)", "-Infinity"),
+        ("exp(1000)", "Infinity"),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        assert_eq!(evaluate(source).unwrap().as_str(), expected, "{}", source);
+    }
+}
+
+#[test]
+fn test_error_policy_raises_located_runtime_errors() {
+    // GIVEN
+    let cases = [
+        ("1 / 0", "Division by zero."),
+        ("0.0 / 0", "Division by zero."),
+        ("1.5 % 0", "Division by zero."),
+        ("5 ~/ 0.0", "Division by zero."),
+        // integers raise the same error under either policy
+        ("1 ~/ 0", "Division by zero."),
+        ("1 % 0", "Division by zero."),
+        ("(-8.0) ** 0.5", "-8.0 ** 0.5 is not a finite number."),
+        ("sqrt(-1)", "sqrt(-1) is not a finite number."),
+        ("log(0.0)", "log(0.0) is not a finite number."),
+        ("sqrt(\"4\")", "Argument of 'sqrt' must be a number, got 4."),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        match evaluate_strict(source) {
+            Err(LoxError::Runtime(e)) => assert_eq!(e.message(), expected, "{}", source),
+            other => panic!("expected a runtime error for {}, got {:?}", source, other),
+        }
+    }
+    assert_eq!(evaluate_strict("1 / 4").unwrap().as_str(), "0.25");
+    assert!(matches!(
+        evaluate("1 ~/ 0"),
+        Err(LoxError::Runtime(e)) if e.message() == "Division by zero."
+    ));
+}
+
+#[test]
+fn test_error_policy_errors_can_be_caught_and_are_not_folded_away() {
+    // GIVEN
+    let source = r#"var zero = 0;
+try {
+  print 1;
+  print 1 / zero;
+} catch (e) {
+  print " ${errorMessage(e)} line ${errorLine(e)}";
+}
+try {
+  print 2.0 / 0;
+} catch (e) {
+  print " ${errorMessage(e)} line ${errorLine(e)}";
+}"#;
+
+    // WHEN
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.set_numeric_policy(NumericPolicy::Error);
+    interpreter.set_opt_level(OptLevel::Full);
+    run(source, &mut interpreter).unwrap();
+    let output = String::from_utf8(interpreter.get_outpout()).unwrap();
+
+    // THEN
+    assert_eq!(
+        output,
+        "1 Division by zero. line 4 Division by zero. line 9"
+    );
+}
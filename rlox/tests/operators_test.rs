@@ -0,0 +1,263 @@
+// tests/operators_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::frontend::lox_value::LoxValue;
+use rulox::tree_walker::ast_printer::to_sexpr;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::{parse, run};
+use rulox::LoxError;
+
+fn evaluate(source: &str) -> Result<LoxValue, LoxError> {
+    Interpreter::new().evaluate_in_frame(source, 0)
+}
+
+fn output_of(source: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    run(source, &mut interpreter).unwrap();
+    String::from_utf8(interpreter.get_outpout()).unwrap()
+}
+
+fn sexpr(source: &str) -> String {
+    to_sexpr(&parse(source).unwrap())
+}
+
+#[test]
+fn test_conditional_precedence_and_associativity() {
+    // GIVEN
+    let sources = [
+        "a ? b : c ? d : e;",
+        "a or b ? c : d;",
+        "x = a ? b : c;",
+        "a ? b = 1 : c;",
+    ];
+
+    // WHEN
+    let printed: Vec<String> = sources.iter().map(|source| sexpr(source)).collect();
+
+    // THEN
+    assert_eq!(
+        printed,
+        [
+            "(; (?: a b (?: c d e)))",
+            "(; (?: (or a b) c d))",
+            "(; (= x (?: a b c)))",
+            "(; (?: a (= b 1) c))",
+        ]
+    );
+}
+
+#[test]
+fn test_conditional_only_evaluates_the_chosen_branch() {
+    // GIVEN
+    let cases = [
+        ("true ? 1 : undefined", "1"),
+        ("nil ? undefined : 2", "2"),
+        ("0 ? \"zero is truthy\" : \"\"", "zero is truthy"),
+        ("1 < 2 ? 1 > 2 ? \"a\" : \"b\" : \"c\"", "b"),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        assert_eq!(evaluate(source).unwrap().as_str(), expected, "{}", source);
+    }
+}
+
+#[test]
+fn test_conditional_without_colon_is_a_syntax_error() {
+    // GIVEN
+    let source = "print a ? b;";
+
+    // WHEN
+    let result = parse(source);
+
+    // THEN
+    assert!(matches!(result, Err(LoxError::ParserError(_))));
+}
+
+#[test]
+fn test_comma_evaluates_left_to_right_and_keeps_the_last_value() {
+    // GIVEN
+    let source = r#"var a = 0;
+var b = (a = a + 1, a = a * 10, a + 2);
+print a;
+print b;"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(output, "1012");
+}
+
+#[test]
+fn test_comma_still_separates_arguments() {
+    // GIVEN
+    let source = r#"fun pair(a, b) { return "${a}/${b}"; }
+print pair(1, 2);
+print pair((1, 2), 3);
+var j = 10;
+for (var i = 0; i < 3; i = i + 1, j = j - 1) print " ${i}:${j}";"#;
+
+    // WHEN
+    let output = output_of(source);
+    let printed = sexpr("f(a, (b, c));");
+
+    // THEN
+    assert_eq!(output, "1/22/3 0:10 1:9 2:8");
+    assert_eq!(printed, "(; (call f a (group (, b c))))");
+}
+
+#[test]
+fn test_compound_assignment() {
+    // GIVEN
+    let source = r#"var x = 10;
+x -= 3;
+x *= 2;
+x /= 4;
+var s = "a";
+s += "b" + "c";
+{
+  var local = 1;
+  print local += 1;
+}
+print x;
+print s;"#;
+
+    // WHEN
+    let output = output_of(source);
+    let printed = sexpr("x += y * 2;");
+
+    // THEN
+    assert_eq!(output, "23.5abc");
+    assert_eq!(printed, "(; (= x (+ x (* y 2))))");
+}
+
+#[test]
+fn test_prefix_and_postfix_increments() {
+    // GIVEN
+    let source = r#"var i = 0;
+print i++;
+print i;
+print ++i;
+print i--;
+print --i;
+fun countdown(n) {
+  while (n > 0) print n--;
+  return n;
+}
+print countdown(3);
+var f = 1.5;
+print ++f;
+for (var k = 0; k < 3; k++) print -k;"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(output, "0122032102.50-1-2");
+}
+
+#[test]
+fn test_invalid_assignment_targets_are_reported_with_their_line() {
+    // GIVEN
+    let sources = [
+        "1 = 2;",
+        "var a;\nf() += 1;",
+        "\n\n++1;",
+        "a++ ++;",
+        "var a;\n(a) = 1;",
+    ];
+
+    // WHEN
+    let lines: Vec<usize> = sources
+        .iter()
+        .map(|source| match parse(source) {
+            Err(LoxError::ParserError(e)) => {
+                assert_eq!(e.message(), "Invalid assignment target.", "{}", source);
+                e.line()
+            }
+            other => panic!("expected a syntax error, got {:?}", other.map(|_| ())),
+        })
+        .collect();
+
+    // THEN
+    assert_eq!(lines, [1, 2, 3, 1, 2]);
+}
+
+#[test]
+fn test_exponent_is_right_associative_and_binds_tighter_than_unary() {
+    // GIVEN
+    let cases = [
+        ("2 ** 10", "1024"),
+        ("2 ** 3 ** 2", "512"),
+        ("-2 ** 2", "-4"),
+        ("2 ** -1", "0.5"),
+        ("2 * 3 ** 2", "18"),
+        ("4.0 ** 0.5", "2.0"),
+        ("(-8) ** 3", "-512"),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        assert_eq!(evaluate(source).unwrap().as_str(), expected, "{}", source);
+    }
+}
+
+#[test]
+fn test_bitwise_operators_and_their_precedence() {
+    // GIVEN
+    let cases = [
+        ("6 & 3", "2"),
+        ("6 | 3", "7"),
+        ("6 ^ 3", "5"),
+        ("~5", "-6"),
+        ("1 << 4", "16"),
+        ("-16 >> 2", "-4"),
+        // shifts bind looser than `+`, `&` tighter than `^` tighter than `|`
+        ("1 + 2 << 1", "6"),
+        ("1 | 6 ^ 3 & 5", "7"),
+        // all of them bind tighter than comparisons
+        ("6 & 3 == 2", "true"),
+        ("1 << 2 > 3", "true"),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        assert_eq!(evaluate(source).unwrap().as_str(), expected, "{}", source);
+    }
+}
+
+#[test]
+fn test_bitwise_operators_and_exponent_errors() {
+    // GIVEN
+    let cases = [
+        (
+            "1.0 & 1",
+            "Operands of '&' must be integers, got 1.0 and 1.",
+        ),
+        (
+            "true ^ 1",
+            "Operands of '^' must be integers, got true and 1.",
+        ),
+        ("~1.5", "Operand of '~' must be an integer, got 1.5."),
+        ("1 << 64", "Shift amount must be between 0 and 63, got 64."),
+        ("1 >> -1", "Shift amount must be between 0 and 63, got -1."),
+        ("2 ** 63", "Integer overflow in 2 ** 63."),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, expected) in cases {
+        match evaluate(source) {
+            Err(LoxError::Interpreter(e)) => assert_eq!(e.message(), expected, "{}", source),
+            other => panic!("expected an error for {}, got {:?}", source, other),
+        }
+    }
+}
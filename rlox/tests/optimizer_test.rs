@@ -0,0 +1,131 @@
+// tests/optimizer_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::tree_walker::ast_printer::to_sexpr;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::tree_walker::optimizer::{optimize, OptLevel};
+use rulox::user_interface::{parse, run};
+use rulox::LoxError;
+
+fn optimized(source: &str, level: OptLevel) -> String {
+    to_sexpr(&optimize(parse(source).unwrap(), level))
+}
+
+fn output_of(source: &str, level: OptLevel) -> Result<String, LoxError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.set_opt_level(level);
+    run(source, &mut interpreter)?;
+    Ok(String::from_utf8(interpreter.get_outpout()).unwrap())
+}
+
+#[test]
+fn test_constant_operators_are_folded() {
+    // GIVEN
+    let source = "var a = (2 * 3) + 1;\nprint \"a\" + \"b\";\nprint -(1.5);\nprint x + 1 * 2;";
+
+    // WHEN
+    let none = optimized(source, OptLevel::None);
+    let fold = optimized(source, OptLevel::Fold);
+
+    // THEN
+    assert_eq!(none, to_sexpr(&parse(source).unwrap()));
+    assert_eq!(
+        fold,
+        "(var a = 7)\n(print \"ab\")\n(print -1.5)\n(print (+ x 2))"
+    );
+}
+
+#[test]
+fn test_failing_operators_are_left_for_runtime() {
+    // GIVEN
+    let source = "print 1 ~/ 0;\nprint \"a\" - 1;";
+    let unreached = "if (false) print 1 ~/ 0;\nprint \"done\";";
+
+    // WHEN
+    let folded = optimized(source, OptLevel::Full);
+    let result = output_of(source, OptLevel::Full);
+    let unreached = output_of(unreached, OptLevel::Full);
+
+    // THEN
+    assert_eq!(folded, to_sexpr(&parse(source).unwrap()));
+    assert!(matches!(result, Err(LoxError::Runtime(_))));
+    assert_eq!(unreached.unwrap(), "done");
+}
+
+#[test]
+fn test_dead_code_is_only_dropped_at_full() {
+    // GIVEN
+    let source = r#"if (true) print "then"; else print "else";
+if (nil) print "never";
+while (false) print "never";
+"no-op";
+1 + 2;"#;
+
+    // WHEN
+    let fold = optimized(source, OptLevel::Fold);
+    let full = optimized(source, OptLevel::Full);
+
+    // THEN
+    assert_eq!(fold.lines().count(), 5);
+    assert_eq!(full, to_sexpr(&parse("print \"then\";").unwrap()));
+}
+
+#[test]
+fn test_logical_operators_with_a_constant_left_operand() {
+    // GIVEN
+    let source = "print nil or x;\nprint 1 and x;\nprint false and x;\nprint \"a\" or x;";
+
+    // WHEN
+    let folded = optimized(source, OptLevel::Fold);
+
+    // THEN
+    assert_eq!(
+        folded,
+        to_sexpr(&parse("print x;\nprint x;\nprint false;\nprint \"a\";").unwrap())
+    );
+}
+
+#[test]
+fn test_programs_print_the_same_at_every_level() {
+    // GIVEN
+    let source = r#"fun f(n) {
+  if (n < 2 * 1) return n;
+  return f(n - 1) + f(n - 2);
+}
+var total = 0;
+for (var i = 0; i < 10; i = i + 1) {
+  if (false or i > 4 + 1) total = total + f(i);
+  while (false) total = 0;
+}
+print total;
+print !(1 == 1.0) and "x";"#;
+
+    // WHEN
+    let outputs: Vec<String> = [OptLevel::None, OptLevel::Fold, OptLevel::Full]
+        .into_iter()
+        .map(|level| output_of(source, level).unwrap())
+        .collect();
+
+    // THEN
+    assert_eq!(outputs[0], outputs[1]);
+    assert_eq!(outputs[0], outputs[2]);
+}
+
+#[test]
+fn test_constant_conditionals_and_commas_are_folded() {
+    // GIVEN
+    let source = "print true ? x : y;\nprint nil ? x : 1 + 1;\nprint (1, x);\nprint (f(), x);";
+
+    // WHEN
+    let folded = optimized(source, OptLevel::Fold);
+
+    // THEN
+    assert_eq!(
+        folded,
+        "(print x)\n(print 2)\n(print (group x))\n(print (group (, (call f) x)))"
+    );
+}
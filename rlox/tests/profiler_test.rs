@@ -0,0 +1,83 @@
+// tests/profiler_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::run;
+
+const FIB: &str = r#"fun fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 2) + fib(n - 1);
+}
+fun twice(n) {
+  return fib(n) + fib(n);
+}
+print twice(3);"#;
+
+#[test]
+fn test_profile_counts_calls_and_lines() {
+    // GIVEN
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.enable_profiler();
+
+    // WHEN
+    run(FIB, &mut interpreter).unwrap();
+    let profile = interpreter.take_profile().unwrap();
+
+    // THEN
+    // fib(3) makes 5 calls, twice calls it two times
+    assert_eq!(profile.function("fib").unwrap().calls, 10);
+    assert_eq!(profile.function("twice").unwrap().calls, 1);
+    // the `if` runs on every call, `return n` on the 6 calls that hit the base case
+    assert_eq!(profile.lines[&2], 16);
+    assert_eq!(profile.lines[&3], 4);
+    assert_eq!(profile.lines[&8], 1);
+
+    let twice = profile.function("twice").unwrap();
+    assert!(twice.inclusive >= twice.exclusive);
+    assert!(twice.inclusive >= profile.function("fib").unwrap().inclusive);
+}
+
+#[test]
+fn test_folded_stacks() {
+    // GIVEN
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.enable_profiler();
+
+    // WHEN
+    run(FIB, &mut interpreter).unwrap();
+    let folded = interpreter.take_profile().unwrap().folded_stacks();
+
+    // THEN
+    let stacks: Vec<&str> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert_eq!(
+        stacks,
+        vec![
+            "<script>",
+            "<script>;twice",
+            "<script>;twice;fib",
+            "<script>;twice;fib;fib",
+            "<script>;twice;fib;fib;fib",
+        ]
+    );
+}
+
+#[test]
+fn test_profiler_is_off_by_default() {
+    // GIVEN
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+
+    // WHEN
+    run(FIB, &mut interpreter).unwrap();
+
+    // THEN
+    assert!(interpreter.take_profile().is_none());
+}
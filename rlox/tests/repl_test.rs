@@ -0,0 +1,202 @@
+// tests/repl_test.rs
+
+extern crate rulox;
+
+use std::panic;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rulox::frontend::lox_callable::LoxCallable;
+use rulox::frontend::lox_value::LoxValue;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::repl::{complete, is_incomplete, ReplSession};
+use rulox::LoxError;
+
+// Native function with a bug, stands in for anything that panics inside the interpreter
+#[derive(Debug)]
+struct Explode {}
+
+impl LoxCallable for Explode {
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _: &mut Interpreter, _: Vec<LoxValue>) -> Result<LoxValue, LoxError> {
+        panic!("boom")
+    }
+
+    fn name(&self) -> &str {
+        "explode"
+    }
+}
+
+#[test]
+fn test_incomplete_input() {
+    // GIVEN
+    let open = [
+        "fun add(a, b) {\n",
+        "print (1 +\n",
+        "print \"hello\n",
+        "{ { }\n",
+        "print \"a\\\"\n",
+    ];
+    let complete_input = [
+        "fun add(a, b) { return a + b; }\n",
+        "print \"{\";\n",
+        "print 1; // {\n",
+        "print \"a\\\"{\";\n",
+        "}\n",
+    ];
+
+    // WHEN
+    // THEN
+    for source in open {
+        assert!(is_incomplete(source), "{:?} should need more input", source);
+    }
+    for source in complete_input {
+        assert!(!is_incomplete(source), "{:?} should be complete", source);
+    }
+}
+
+#[test]
+fn test_complete_keywords_and_globals() {
+    // GIVEN
+    let globals = vec!["printer".to_string(), "value".to_string()];
+
+    // WHEN
+    let (start, candidates) = complete("var x = pri", 11, &globals);
+
+    // THEN
+    assert_eq!(start, 8);
+    assert_eq!(candidates, vec!["print", "printer"]);
+}
+
+#[test]
+fn test_complete_nothing_without_prefix() {
+    // GIVEN
+    let globals = vec!["value".to_string()];
+
+    // WHEN
+    let (start, candidates) = complete("print ", 6, &globals);
+
+    // THEN
+    assert_eq!(start, 6);
+    assert!(candidates.is_empty());
+}
+
+#[test]
+fn test_session_echoes_expressions() {
+    // GIVEN
+    let mut session = ReplSession::default();
+
+    // WHEN
+    let declared = session.eval("var x = 4;").unwrap();
+    let bare = session.eval("x * 2").unwrap();
+    let assigned = session.eval("x = \"four\";").unwrap();
+
+    // THEN
+    assert_eq!(declared, "");
+    assert_eq!(bare, "8");
+    assert_eq!(assigned, "\"four\"");
+}
+
+#[test]
+fn test_session_meta_commands() {
+    // GIVEN
+    let mut session = ReplSession::default();
+    session.eval("fun add(a, b) { return a + b; }").unwrap();
+    session.eval("var total = add(1, 2);").unwrap();
+
+    // WHEN
+    let env = session.eval(":env").unwrap();
+    let tokens = session.eval(":tokens a + 1").unwrap();
+    let ast = session.eval(":ast -a").unwrap();
+    let precedence = session.eval(":ast 1 + 2 * 3").unwrap();
+    session.eval(":reset").unwrap();
+
+    // THEN
+    assert_eq!(env, "add = <fn add>\ntotal = 3");
+    assert_eq!(
+        tokens,
+        "1:0 Identifier 'a'\n1:2 Plus '+'\n1:4 Number '1'\n1:5 Eof ''"
+    );
+    assert_eq!(ast, "(- a)");
+    assert_eq!(precedence, "(+ 1 (* 2 3))");
+    assert_eq!(session.eval(":env").unwrap(), "");
+    assert!(session.eval("total").is_err());
+}
+
+#[test]
+fn test_session_load() {
+    // GIVEN
+    let path = std::env::temp_dir().join("repl_load.lox");
+    std::fs::write(&path, "var loaded = 1 + 1;").unwrap();
+    let mut session = ReplSession::default();
+
+    // WHEN
+    session
+        .eval(&format!(":load {}", path.to_string_lossy()))
+        .unwrap();
+
+    // THEN
+    assert_eq!(session.eval("loaded").unwrap(), "2");
+    assert!(session.eval(":load /does/not/exist.lox").is_err());
+}
+
+#[test]
+fn test_session_survives_errors() {
+    // GIVEN
+    let mut session = ReplSession::default();
+    session.eval("var kept = 1;").unwrap();
+
+    // WHEN
+    let stray_return = session.eval("return 1;");
+    let parse_error = session.eval("var = ;");
+    let runtime_error = session.eval("-\"a\"");
+
+    // THEN
+    match stray_return {
+        Err(LoxError::Runtime(e)) => assert_eq!(e.message(), "Can't return from top-level code."),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(matches!(parse_error, Err(LoxError::ParserError(_))));
+    assert!(matches!(runtime_error, Err(LoxError::Interpreter(_))));
+    assert_eq!(session.eval("kept").unwrap(), "1");
+}
+
+#[test]
+fn test_session_survives_native_panic() {
+    // GIVEN
+    let mut session = ReplSession::default();
+    session
+        .interpreter
+        .globals
+        .borrow_mut()
+        .define("explode", LoxValue::Function(Rc::new(Explode {})));
+    session
+        .eval("fun outer(n) { var local = n; return explode(); }")
+        .unwrap();
+    static REPORTED: AtomicUsize = AtomicUsize::new(0);
+    panic::set_hook(Box::new(|_| {
+        REPORTED.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    // WHEN
+    let result = session.eval("outer(1)");
+    let reported_by_session = REPORTED.load(Ordering::SeqCst);
+    let _ = panic::catch_unwind(|| panic!("outside of the session"));
+    let _ = panic::take_hook();
+
+    // THEN
+    match result {
+        Err(LoxError::Runtime(e)) => assert_eq!(e.message(), "Internal error: boom"),
+        other => panic!("unexpected result {:?}", other),
+    }
+    // back in the global scope, not stuck inside `outer`
+    assert!(session.eval("local").is_err());
+    assert_eq!(session.interpreter.call_stack().len(), 1);
+    assert_eq!(session.eval("1 + 1").unwrap(), "2");
+    // the session silences the hook only while it runs the entry
+    assert_eq!(reported_by_session, 0);
+    assert_eq!(REPORTED.load(Ordering::SeqCst), 1);
+}
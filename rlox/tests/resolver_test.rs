@@ -0,0 +1,110 @@
+// tests/resolver_test.rs
+
+extern crate rulox;
+
+use std::fs;
+use std::io;
+
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::{parse, run};
+
+fn output_of(source: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    run(source, &mut interpreter).unwrap();
+    String::from_utf8(interpreter.get_outpout()).unwrap()
+}
+
+#[test]
+fn test_locals_shadow_outer_scopes() {
+    // GIVEN
+    let source = r#"var a = "global ";
+{
+  var a = "outer ";
+  {
+    // the initializer still sees the outer `a`, the inner one isn't declared yet
+    var b = a;
+    var a = "inner ";
+    print b;
+    print a;
+  }
+  print a;
+}
+print a;"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(output, "outer inner outer global ");
+}
+
+#[test]
+fn test_functions_only_see_their_own_locals_and_globals() {
+    // GIVEN
+    let source = r#"var x = "global";
+{
+  var x = "local";
+  fun f() { return x; }
+  print f();
+}"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(output, "global");
+}
+
+#[test]
+fn test_locals_in_loops_calls_and_catch_blocks() {
+    // GIVEN
+    let source = r#"fun sum(n) {
+  var total = 0;
+  for (var i = 0; i < n; i = i + 1) {
+    var square = i * i;
+    total = total + square;
+  }
+  // declaring a name again in the same scope reuses it
+  var total = total + 0;
+  return total;
+}
+print sum(5);
+try { throw 1; } catch (e) { var next = e + 1; print next; }"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(output, "302");
+}
+
+#[test]
+fn test_import_into_a_block_is_looked_up_by_name() {
+    // GIVEN
+    let dir = std::env::temp_dir().join("lox_resolver_import");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("module.lox"), "var x = \"module \";").unwrap();
+    let main = dir.join("main.lox");
+    let source = r#"var x = "global ";
+{
+  var y = "local ";
+  import "module.lox";
+  var z = "after";
+  print x;
+  print y;
+  print z;
+}"#;
+
+    // WHEN
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    interpreter.set_script_path(&main);
+    interpreter.interpret(parse(source).unwrap()).unwrap();
+
+    // THEN
+    // the module bound `x` in the block and took the slots after `y`
+    let output = String::from_utf8(interpreter.get_outpout()).unwrap();
+    assert_eq!(output, "module local after");
+}
@@ -0,0 +1,125 @@
+// tests/scanner_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::frontend::scanner::Scanner;
+use rulox::frontend::token::Token;
+use rulox::frontend::token_type::TokenType;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::run;
+
+fn scan(source: &str) -> Vec<Token> {
+    Scanner::build_scanner(source).scan_tokens().unwrap()
+}
+
+#[test]
+fn test_non_ascii_strings_and_comments() {
+    // GIVEN
+    let source = "// naïve comment ✓\nprint \"héllo wörld 🦀\"; /* ünïcode */ print 1;";
+
+    // WHEN
+    let tokens = scan(source);
+
+    // THEN
+    let lexemes: Vec<&str> = tokens.iter().map(|token| token.lexeme.as_str()).collect();
+    assert_eq!(
+        lexemes,
+        ["print", "héllo wörld 🦀", ";", "print", "1", ";", ""]
+    );
+    // columns count characters, not bytes
+    assert_eq!((tokens[2].line, tokens[2].column), (2, 21));
+    assert_eq!(tokens[3].column, 37);
+}
+
+#[test]
+fn test_unicode_identifiers() {
+    // GIVEN
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    let source = "var größe = 2; var café_1 = größe * 3; print café_1;".to_string();
+
+    // WHEN
+    run(&source, &mut interpreter).unwrap();
+
+    // THEN
+    assert_eq!(scan(&source)[1].token_type, TokenType::Identifier);
+    assert_eq!(scan(&source)[1].lexeme, "größe");
+    assert_eq!(String::from_utf8(interpreter.get_outpout()).unwrap(), "6");
+}
+
+#[test]
+fn test_large_input_is_scanned_in_one_pass() {
+    // GIVEN
+    // a couple of megabytes, a scanner that restarts at the beginning of the source for every
+    // character would not finish
+    let source = "var ä = \"ö\"; // ü\n".repeat(100_000);
+
+    // WHEN
+    let tokens = scan(&source);
+
+    // THEN
+    assert_eq!(tokens.len(), 5 * 100_000 + 1);
+    assert_eq!(tokens[tokens.len() - 2].line, 100_000);
+}
+
+#[test]
+fn test_operators_take_the_longest_match() {
+    // GIVEN
+    let source = "a+++b -- c -= d *= e /= f ** g << h >> i ~/ ~j // k\n? :";
+
+    // WHEN
+    let tokens = scan(source);
+
+    // THEN
+    let types: Vec<TokenType> = tokens.into_iter().map(|token| token.token_type).collect();
+    assert_eq!(
+        types,
+        [
+            TokenType::Identifier,
+            TokenType::PlusPlus,
+            TokenType::Plus,
+            TokenType::Identifier,
+            TokenType::MinusMinus,
+            TokenType::Identifier,
+            TokenType::MinusEqual,
+            TokenType::Identifier,
+            TokenType::StarEqual,
+            TokenType::Identifier,
+            TokenType::SlashEqual,
+            TokenType::Identifier,
+            TokenType::StarStar,
+            TokenType::Identifier,
+            TokenType::LessLess,
+            TokenType::Identifier,
+            TokenType::GreaterGreater,
+            TokenType::Identifier,
+            TokenType::TildeSlash,
+            TokenType::Tilde,
+            TokenType::Identifier,
+            TokenType::Question,
+            TokenType::Colon,
+            TokenType::Eof,
+        ]
+    );
+}
+
+#[test]
+fn test_double_slash_after_an_operand_still_starts_a_comment() {
+    // GIVEN
+    let source = r#"var x = 1 // one
+foo(a, b // note
+) // closed
+var y = x ~/ 2; // half"#;
+
+    // WHEN
+    let tokens = scan(source);
+
+    // THEN
+    let lexemes: Vec<&str> = tokens.iter().map(|token| token.lexeme.as_str()).collect();
+    assert_eq!(
+        lexemes.join(" "),
+        "var x = 1 foo ( a , b ) var y = x ~/ 2 ; "
+    );
+}
@@ -0,0 +1,112 @@
+// tests/strings_test.rs
+
+extern crate rulox;
+
+use std::io;
+
+use rulox::frontend::scanner::Scanner;
+use rulox::frontend::token_type::TokenType;
+use rulox::tree_walker::ast_printer::to_sexpr;
+use rulox::tree_walker::interpreter::Interpreter;
+use rulox::user_interface::{parse, run};
+use rulox::{Loc, LoxError};
+
+fn output_of(source: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(Box::new(io::sink()));
+    run(source, &mut interpreter).unwrap();
+    String::from_utf8(interpreter.get_outpout()).unwrap()
+}
+
+#[test]
+fn test_escape_sequences() {
+    // GIVEN
+    let source = r#"print "tab\tquote\" backslash\\ newline\n crab \u{1F980} \${not} nul\0.";"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(
+        output,
+        "tab\tquote\" backslash\\ newline\n crab 🦀 ${not} nul\0."
+    );
+}
+
+#[test]
+fn test_invalid_escapes_are_located() {
+    // GIVEN
+    let sources = [
+        ("print 1;\nprint \"bad \\q\";", 2, "\\q"),
+        ("print \"\\u{110000}\";", 1, "\\u{110000}"),
+        ("print \"\\u1234\";", 1, "\\u"),
+    ];
+
+    // WHEN
+    // THEN
+    for (source, line, escape) in sources {
+        let result = Scanner::build_scanner(source).scan_tokens();
+        let Err(LoxError::ScannerError(error)) = result else {
+            panic!("{:?} should not scan", source);
+        };
+        assert_eq!(error.line(), line);
+        assert!(matches!(error.location(), Loc::Lexeme(lexeme) if lexeme == escape));
+        assert_eq!(error.message(), "Invalid escape sequence");
+    }
+}
+
+#[test]
+fn test_interpolation_stringifies_any_value() {
+    // GIVEN
+    let source = r#"var name = "Ada";
+var age = 36;
+fun half(n) { return n / 2; }
+print "Hello ${name}, you are ${age}";
+print "${nil} ${true} ${half(3)} ${"inner ${age + 1}"}${half}";"#;
+
+    // WHEN
+    let output = output_of(source);
+
+    // THEN
+    assert_eq!(output, "Hello Ada, you are 36nil true 1.5 inner 37half");
+}
+
+#[test]
+fn test_interpolation_is_lowered_to_concatenation() {
+    // GIVEN
+    let source = "var str = 1;\nprint \"a ${str} b\";".to_string();
+
+    // WHEN
+    let tokens = Scanner::build_scanner(&source).scan_tokens().unwrap();
+    let program = parse(&source).unwrap();
+
+    // THEN
+    let types: Vec<&TokenType> = tokens[6..10].iter().map(|t| &t.token_type).collect();
+    assert_eq!(
+        types,
+        [
+            &TokenType::Interpolation,
+            &TokenType::Identifier,
+            &TokenType::String,
+            &TokenType::Semicolon
+        ]
+    );
+    assert_eq!(
+        to_sexpr(&program).lines().nth(1),
+        Some("(print (+ (+ \"a \" (call str str)) \" b\"))")
+    );
+    // shadowing `str` doesn't change what interpolation does
+    assert_eq!(output_of(&source), "a 1 b");
+}
+
+#[test]
+fn test_unterminated_interpolation() {
+    // GIVEN
+    let source = "print \"a ${1 + 2\";".to_string();
+
+    // WHEN
+    let result = Scanner::build_scanner(&source).scan_tokens();
+
+    // THEN
+    assert!(matches!(result, Err(LoxError::ScannerError(_))));
+}
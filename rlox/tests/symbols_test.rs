@@ -0,0 +1,65 @@
+// tests/symbols_test.rs
+
+extern crate rulox;
+
+use rulox::frontend::scanner::Scanner;
+use rulox::frontend::symbol::Symbol;
+use rulox::tree_walker::parser::{Expr, Stmt};
+use rulox::user_interface::parse;
+
+#[test]
+fn test_identifiers_are_interned() {
+    // GIVEN
+    let tokens = Scanner::build_scanner("count = count + other;")
+        .scan_tokens()
+        .unwrap();
+
+    // WHEN
+    let symbols: Vec<Symbol> = [0, 2, 4].iter().map(|&i| tokens[i].symbol()).collect();
+
+    // THEN
+    assert_eq!(symbols[0], symbols[1]);
+    assert_ne!(symbols[0], symbols[2]);
+    assert_eq!(symbols[0], Symbol::intern("count"));
+    assert_eq!(symbols[2].as_str(), "other");
+}
+
+#[test]
+fn test_names_in_use_survive_a_sweep() {
+    // GIVEN
+    let kept = Symbol::intern("kept");
+
+    // WHEN
+    // enough short lived names to make the interner drop the unused ones a few times
+    for i in 0..10_000 {
+        Symbol::intern(&format!("temporary{}", i));
+    }
+
+    // THEN
+    assert_eq!(kept, Symbol::intern("kept"));
+    assert_eq!(kept.as_str(), "kept");
+}
+
+#[test]
+fn test_expressions_are_stored_in_the_program_arena() {
+    // GIVEN
+    let program = parse("a = a;").unwrap();
+
+    // WHEN
+    let assign = match &program.statements[0] {
+        Stmt::Expression(stmt) => match &program.ast[stmt.expression] {
+            Expr::Assign(assign) => assign,
+            other => panic!("expected an assignment, got {:?}", other),
+        },
+        other => panic!("expected an expression statement, got {:?}", other),
+    };
+
+    // THEN
+    // one node for the assignment and one for the variable it reads, neither resolved yet
+    assert_eq!(program.ast.len(), 2);
+    assert!(assign.slot.is_none());
+    assert!(matches!(
+        &program.ast[assign.value],
+        Expr::Variable(variable) if variable.name.lexeme == "a" && variable.slot.is_none()
+    ));
+}
@@ -0,0 +1,148 @@
+// tests/token_stream_test.rs
+
+extern crate rulox;
+
+use rulox::frontend::scanner::Scanner;
+use rulox::frontend::token_type::TokenType;
+use rulox::tree_walker::parser::Parser;
+use rulox::user_interface::parse;
+use rulox::user_interface::repl::OpenDelimiters;
+use rulox::LoxError;
+
+#[test]
+fn test_tokens_are_scanned_on_demand() {
+    // GIVEN
+    // the invalid character is only reached by the third token
+    let source = "print x; @";
+
+    // WHEN
+    let mut scanner = Scanner::build_scanner(source);
+    let first = scanner.next().unwrap().unwrap();
+    let second = scanner.next().unwrap().unwrap();
+
+    // THEN
+    assert_eq!(first.token_type, TokenType::Print);
+    assert_eq!(second.lexeme, "x");
+    // the lexemes point into the source instead of being copies of it
+    assert!(source
+        .as_bytes()
+        .as_ptr_range()
+        .contains(&second.lexeme.as_ptr()));
+}
+
+#[test]
+fn test_scanner_ends_after_eof_or_the_first_error() {
+    // GIVEN
+    let valid = Scanner::build_scanner("1 + 2");
+    let invalid = Scanner::build_scanner("1 @ 2 @");
+
+    // WHEN
+    let valid: Vec<_> = valid.collect();
+    let invalid: Vec<_> = invalid.collect();
+
+    // THEN
+    assert_eq!(valid.len(), 4);
+    assert_eq!(
+        valid.last().unwrap().as_ref().unwrap().token_type,
+        TokenType::Eof
+    );
+    assert_eq!(invalid.len(), 2);
+    assert!(matches!(invalid[1], Err(LoxError::ScannerError(_))));
+}
+
+#[test]
+fn test_scanner_errors_win_over_syntax_errors() {
+    // GIVEN
+    // the missing expression comes before the invalid character
+    let source = "print ;\nprint 1; @";
+
+    // WHEN
+    let result = parse(source);
+
+    // THEN
+    match result {
+        Err(LoxError::ScannerError(e)) => assert_eq!(e.line(), 2),
+        other => panic!("expected a scanner error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_recovering_parse_keeps_statements_before_a_scanner_error() {
+    // GIVEN
+    let source = "var a = 1;\nprint a;\nvar b = @;";
+
+    // WHEN
+    let (program, errors) = Parser::from_source(source).parse_recovering();
+
+    // THEN
+    // the cut off `var b` isn't reported on top of the invalid character
+    assert_eq!(program.statements.len(), 2);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], LoxError::ScannerError(_)));
+}
+
+#[test]
+fn test_open_delimiters_are_tracked_across_lines() {
+    // GIVEN
+    let mut delimiters = OpenDelimiters::default();
+
+    // WHEN
+    delimiters.feed("fun f() {\n");
+    let after_first = delimiters.is_incomplete();
+    delimiters.feed("  print \"a } \\\n");
+    let inside_string = delimiters.is_incomplete();
+    delimiters.feed("b\"; // }\n");
+    let after_string = delimiters.is_incomplete();
+    delimiters.feed("}\n");
+
+    // THEN
+    assert!(after_first);
+    assert!(inside_string);
+    assert!(after_string);
+    assert!(!delimiters.is_incomplete());
+}
+
+#[test]
+fn test_open_delimiters_skip_trailing_comments() {
+    // GIVEN
+    let sources = [
+        "print 7 ~/ (2",
+        "print 7 // (2",
+        "print (a) // (b",
+        "// (\n",
+    ];
+
+    // WHEN
+    let incomplete: Vec<bool> = sources
+        .iter()
+        .map(|source| {
+            let mut delimiters = OpenDelimiters::default();
+            delimiters.feed(source);
+            delimiters.is_incomplete()
+        })
+        .collect();
+
+    // THEN
+    assert_eq!(incomplete, [true, false, false, false]);
+}
+
+#[test]
+fn test_open_delimiters_track_nested_block_comments() {
+    // GIVEN
+    let mut delimiters = OpenDelimiters::default();
+
+    // WHEN
+    delimiters.feed("print 1; /* outer ( \"\n");
+    let in_comment = delimiters.is_incomplete();
+    delimiters.feed("/* inner */ still { outer\n");
+    let after_inner = delimiters.is_incomplete();
+    delimiters.feed("*/ print (2\n");
+    let after_outer = delimiters.is_incomplete();
+    delimiters.feed("/*/ ) */ );\n");
+
+    // THEN
+    assert!(in_comment);
+    assert!(after_inner);
+    assert!(after_outer);
+    assert!(!delimiters.is_incomplete());
+}